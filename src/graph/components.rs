@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use super::{neighbours,Direction};
+use crate::k2tree::K2tree;
+
+const UNASSIGNED:usize = usize::MAX;
+
+//Component of every node. Components are numbered in order of their smallest node.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Components{
+	labels:Vec<usize>,
+	sizes:Vec<usize>
+}
+
+impl Components{
+	fn from_raw(raw:Vec<usize>)->Self{
+		let mut renumber = vec![UNASSIGNED;raw.len()];
+		let mut sizes = Vec::new();
+		let labels = raw.into_iter().map(|label| {
+			if renumber[label] == UNASSIGNED{
+				renumber[label] = sizes.len();
+				sizes.push(0);
+			}
+			sizes[renumber[label]]+=1;
+			renumber[label]
+		}).collect();
+		Components{
+			labels,
+			sizes
+		}
+	}
+	pub fn get_labels(&self)->&Vec<usize>{
+		&self.labels
+	}
+	//Nodes of each component, by label
+	pub fn get_sizes(&self)->&Vec<usize>{
+		&self.sizes
+	}
+	pub fn label_of(&self,node:usize)->usize{
+		self.labels[node]
+	}
+	pub fn count(&self)->usize{
+		self.sizes.len()
+	}
+	//Number of components of each size
+	pub fn histogram(&self)->BTreeMap<usize,usize>{
+		let mut histogram = BTreeMap::new();
+		for &size in &self.sizes{
+			*histogram.entry(size).or_insert(0)+=1;
+		}
+		histogram
+	}
+}
+
+//Components ignoring the direction of the arcs
+pub fn weakly_connected_components<T>(tree:&K2tree<T>)->Components where T:Eq + Clone{
+	assert!(tree.get_rows() == tree.get_cols(),"graph must be square");
+	let mut labels = vec![UNASSIGNED;tree.get_rows()];
+	let mut stack = Vec::new();
+	for root in 0..tree.get_rows(){
+		if labels[root] != UNASSIGNED{
+			continue;
+		}
+		labels[root] = root;
+		stack.push(root);
+		while let Some(node) = stack.pop(){
+			for neighbour in neighbours(tree,node,Direction::Undirected){
+				if labels[neighbour] == UNASSIGNED{
+					labels[neighbour] = root;
+					stack.push(neighbour);
+				}
+			}
+		}
+	}
+	Components::from_raw(labels)
+}
+
+//Kosaraju: a depth first pass over rows gives the finishing order, then each node, latest
+//finished first, collects what reaches it through columns
+pub fn strongly_connected_components<T>(tree:&K2tree<T>)->Components where T:Eq + Clone{
+	assert!(tree.get_rows() == tree.get_cols(),"graph must be square");
+	let nodes = tree.get_rows();
+	let mut visited = vec![false;nodes];
+	let mut finished = Vec::with_capacity(nodes);
+	//(node, successors, next successor to try)
+	let mut stack:Vec<(usize,Vec<usize>,usize)> = Vec::new();
+	for root in 0..nodes{
+		if visited[root]{
+			continue;
+		}
+		visited[root] = true;
+		stack.push((root,neighbours(tree,root,Direction::Outgoing),0));
+		while let Some((node,successors,next)) = stack.last_mut(){
+			if *next == successors.len(){
+				finished.push(*node);
+				stack.pop();
+				continue;
+			}
+			let successor = successors[*next];
+			*next+=1;
+			if !visited[successor]{
+				visited[successor] = true;
+				stack.push((successor,neighbours(tree,successor,Direction::Outgoing),0));
+			}
+		}
+	}
+	let mut labels = vec![UNASSIGNED;nodes];
+	let mut pending = Vec::new();
+	for &root in finished.iter().rev(){
+		if labels[root] != UNASSIGNED{
+			continue;
+		}
+		labels[root] = root;
+		pending.push(root);
+		while let Some(node) = pending.pop(){
+			for predecessor in neighbours(tree,node,Direction::Incoming){
+				if labels[predecessor] == UNASSIGNED{
+					labels[predecessor] = root;
+					pending.push(predecessor);
+				}
+			}
+		}
+	}
+	Components::from_raw(labels)
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::k2tree::K2tree;
+	use super::{weakly_connected_components,strongly_connected_components};
+
+	//Cycle 0->1->2->0, arc 2->3, cycle 3<->4, 5->6, 7 isolated
+	fn sample()->K2tree<bool>{
+		let arcs = vec![(0,1),(1,2),(2,0),(2,3),(3,4),(4,3),(5,6)];
+		K2tree::from_triplets(8,8,2,false,arcs.into_iter().map(|(i,j)| (i,j,true)))
+	}
+
+	#[test]
+	fn weak_components(){
+		let components = weakly_connected_components(&sample());
+		assert_eq!(components.get_labels(),&vec![0,0,0,0,0,1,1,2]);
+		assert_eq!(components.get_sizes(),&vec![5,2,1]);
+		assert_eq!(components.histogram().into_iter().collect::<Vec<_>>(),vec![(1,1),(2,1),(5,1)]);
+	}
+
+	#[test]
+	fn strong_components(){
+		let components = strongly_connected_components(&sample());
+		assert_eq!(components.get_labels(),&vec![0,0,0,1,1,2,3,4]);
+		assert_eq!(components.count(),5);
+		assert_eq!(components.histogram().into_iter().collect::<Vec<_>>(),vec![(1,3),(2,1),(3,1)]);
+		let symmetric = K2tree::from_triplets_symmetric(4,2,false,vec![(0,2,true),(3,1,true)]);
+		assert_eq!(strongly_connected_components(&symmetric),weakly_connected_components(&symmetric));
+	}
+}
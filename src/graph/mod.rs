@@ -0,0 +1,201 @@
+use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::Entry;
+
+use crate::k2tree::K2tree;
+
+pub mod pagerank;
+pub mod triangles;
+pub mod components;
+pub use pagerank::{PageRank,Dangling};
+pub use triangles::{triangles_per_node,triangle_count,local_clustering,global_clustering};
+pub use components::{Components,weakly_connected_components,strongly_connected_components};
+
+//Edges are the non-background cells of a square tree: (i,j) is an arc from i to j
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Direction{
+	//Successors, row queries
+	Outgoing,
+	//Predecessors, column queries
+	Incoming,
+	//Both, as if every arc was undirected
+	Undirected
+}
+
+pub(crate) fn neighbours<T>(tree:&K2tree<T>,node:usize,direction:Direction)->Vec<usize> where T:Eq + Clone{
+	match direction{
+		Direction::Outgoing => tree.row(node).into_iter().map(|(j,_)| j).collect(),
+		Direction::Incoming => tree.column(node).into_iter().map(|(i,_)| i).collect(),
+		Direction::Undirected => {
+			let mut both:Vec<usize> = tree.row(node).into_iter().map(|(j,_)| j)
+				.chain(tree.column(node).into_iter().map(|(i,_)| i))
+				.collect();
+			both.sort_unstable();
+			both.dedup();
+			both
+		}
+	}
+}
+
+fn check_node<T>(tree:&K2tree<T>,node:usize) where T:Eq + Clone{
+	assert!(tree.get_rows() == tree.get_cols(),"graph must be square");
+	assert!(node<tree.get_rows(),"node overflows graph");
+}
+
+//Breadth first iterator yielding (node,distance) pairs, source first. Only the reached nodes
+//are remembered.
+pub struct Bfs<'a,T> where T:Clone{
+	tree:&'a K2tree<T>,
+	direction:Direction,
+	max_depth:Option<usize>,
+	visited:HashMap<usize,usize>,
+	queue:VecDeque<usize>
+}
+
+impl <'a,T> Bfs<'a,T> where T:Eq + Clone{
+	pub fn new(tree:&'a K2tree<T>,source:usize,direction:Direction)->Self{
+		check_node(tree,source);
+		let mut visited = HashMap::new();
+		visited.insert(source,0);
+		Bfs{
+			tree,
+			direction,
+			max_depth:None,
+			visited,
+			queue:VecDeque::from(vec![source])
+		}
+	}
+	//Nodes further than depth hops are not visited
+	pub fn max_depth(mut self,depth:usize)->Self{
+		self.max_depth = Some(depth);
+		self
+	}
+	pub fn distance_of(&self,node:usize)->Option<usize>{
+		self.visited.get(&node).copied()
+	}
+}
+
+impl <'a,T> Iterator for Bfs<'a,T> where T:Eq + Clone{
+	type Item = (usize,usize);
+	fn next(&mut self)->Option<Self::Item>{
+		let node = self.queue.pop_front()?;
+		let distance = self.visited[&node];
+		if self.max_depth.map_or(true,|depth| distance < depth){
+			for neighbour in neighbours(self.tree,node,self.direction){
+				if let Entry::Vacant(entry) = self.visited.entry(neighbour){
+					entry.insert(distance+1);
+					self.queue.push_back(neighbour);
+				}
+			}
+		}
+		Some((node,distance))
+	}
+}
+
+pub fn bfs<T>(tree:&K2tree<T>,source:usize,direction:Direction)->Bfs<'_,T> where T:Eq + Clone{
+	Bfs::new(tree,source,direction)
+}
+
+//Nodes reachable in 1 to hops steps, sorted by id
+pub fn k_hop<T>(tree:&K2tree<T>,source:usize,hops:usize,direction:Direction)->Vec<usize> where T:Eq + Clone{
+	let mut reached:Vec<usize> = Bfs::new(tree,source,direction).max_depth(hops)
+		.filter(|&(node,_)| node != source)
+		.map(|(node,_)| node)
+		.collect();
+	reached.sort_unstable();
+	reached
+}
+
+//Length of the shortest path from source to target, following arcs forward
+pub fn distance<T>(tree:&K2tree<T>,source:usize,target:usize)->Option<usize> where T:Eq + Clone{
+	check_node(tree,target);
+	Bfs::new(tree,source,Direction::Outgoing)
+		.find(|&(node,_)| node == target)
+		.map(|(_,distance)| distance)
+}
+
+//Same as distance, growing a forward search from source (rows) and a backward one from
+//target (columns) one level at a time, always the smaller frontier first
+pub fn distance_bidirectional<T>(tree:&K2tree<T>,source:usize,target:usize)->Option<usize> where T:Eq + Clone{
+	check_node(tree,source);
+	check_node(tree,target);
+	if source == target{
+		return Some(0);
+	}
+	let mut forward:HashMap<usize,usize> = HashMap::from([(source,0)]);
+	let mut backward:HashMap<usize,usize> = HashMap::from([(target,0)]);
+	let mut forward_frontier = vec![source];
+	let mut backward_frontier = vec![target];
+	while !forward_frontier.is_empty() && !backward_frontier.is_empty(){
+		let expand_forward = forward_frontier.len() <= backward_frontier.len();
+		let (frontier,seen,other,direction) = if expand_forward{
+			(&mut forward_frontier,&mut forward,&backward,Direction::Outgoing)
+		}else{
+			(&mut backward_frontier,&mut backward,&forward,Direction::Incoming)
+		};
+		let mut next = Vec::new();
+		let mut best:Option<usize> = None;
+		for &node in frontier.iter(){
+			let distance = seen[&node];
+			for neighbour in neighbours(tree,node,direction){
+				if let Some(rest) = other.get(&neighbour){
+					let total = distance + 1 + rest;
+					best = Some(best.map_or(total,|best| std::cmp::min(best,total)));
+				}
+				if let Entry::Vacant(entry) = seen.entry(neighbour){
+					entry.insert(distance+1);
+					next.push(neighbour);
+				}
+			}
+		}
+		//Every meeting found while expanding a whole level is as short as any later one
+		if best.is_some(){
+			return best;
+		}
+		*frontier = next;
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::k2tree::K2tree;
+	use super::{bfs,k_hop,distance,distance_bidirectional,Direction};
+
+	//0->1->2->3, 0->4->3, 5->0, 6 isolated
+	fn sample()->K2tree<bool>{
+		let arcs = vec![(0,1),(1,2),(2,3),(0,4),(4,3),(5,0)];
+		K2tree::from_triplets(7,7,2,false,arcs.into_iter().map(|(i,j)| (i,j,true)))
+	}
+
+	#[test]
+	fn bfs_order_and_depth(){
+		let k2tree = sample();
+		assert_eq!(bfs(&k2tree,0,Direction::Outgoing).collect::<Vec<_>>(),vec![(0,0),(1,1),(4,1),(2,2),(3,2)]);
+		assert_eq!(bfs(&k2tree,3,Direction::Incoming).collect::<Vec<_>>(),vec![(3,0),(2,1),(4,1),(1,2),(0,2),(5,3)]);
+		assert_eq!(bfs(&k2tree,0,Direction::Outgoing).max_depth(1).count(),3);
+		assert_eq!(bfs(&k2tree,6,Direction::Undirected).collect::<Vec<_>>(),vec![(6,0)]);
+	}
+
+	#[test]
+	fn neighbourhoods(){
+		let k2tree = sample();
+		assert_eq!(k_hop(&k2tree,0,1,Direction::Outgoing),vec![1,4]);
+		assert_eq!(k_hop(&k2tree,0,2,Direction::Outgoing),vec![1,2,3,4]);
+		assert_eq!(k_hop(&k2tree,0,1,Direction::Undirected),vec![1,4,5]);
+		assert!(k_hop(&k2tree,0,0,Direction::Outgoing).is_empty());
+	}
+
+	#[test]
+	fn distances(){
+		let k2tree = sample();
+		for source in 0..7{
+			for target in 0..7{
+				assert_eq!(distance(&k2tree,source,target),distance_bidirectional(&k2tree,source,target));
+			}
+		}
+		assert_eq!(distance(&k2tree,5,3),Some(3));
+		assert_eq!(distance(&k2tree,3,0),None);
+		assert_eq!(distance_bidirectional(&k2tree,2,2),Some(0));
+	}
+}
@@ -0,0 +1,121 @@
+use crate::k2tree::{K2tree,Weight};
+
+//What happens to the rank of nodes without outgoing weight
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Dangling{
+	//Spread over every node
+	Uniform,
+	//Kept by the node, as if it linked to itself
+	SelfLoop
+}
+
+//Power iteration over the weighted arcs: node i gives rank to j in proportion to (i,j)
+#[derive(Debug,Clone)]
+pub struct PageRank{
+	damping:f64,
+	tolerance:f64,
+	max_iterations:usize,
+	dangling:Dangling
+}
+
+impl Default for PageRank{
+	fn default()->Self{
+		PageRank{
+			damping:0.85,
+			tolerance:1e-9,
+			max_iterations:100,
+			dangling:Dangling::Uniform
+		}
+	}
+}
+
+impl PageRank{
+	pub fn new()->Self{
+		Self::default()
+	}
+	pub fn damping(mut self,damping:f64)->Self{
+		assert!((0.0..=1.0).contains(&damping),"damping must be between 0 and 1");
+		self.damping = damping;
+		self
+	}
+	//Stops once the L1 change of an iteration is below tolerance
+	pub fn tolerance(mut self,tolerance:f64)->Self{
+		self.tolerance = tolerance;
+		self
+	}
+	pub fn max_iterations(mut self,max_iterations:usize)->Self{
+		self.max_iterations = max_iterations;
+		self
+	}
+	pub fn dangling(mut self,dangling:Dangling)->Self{
+		self.dangling = dangling;
+		self
+	}
+
+	//Ranks of every node, adding up to 1. Every iteration is one transposed product.
+	pub fn run<T>(&self,tree:&K2tree<T>)->Vec<f64> where T:Eq + Clone + Weight{
+		assert!(tree.get_rows() == tree.get_cols(),"graph must be square");
+		let nodes = tree.get_rows();
+		let out = tree.spmv(&vec![1.0;nodes]);
+		let teleport = (1.0-self.damping)/nodes as f64;
+		let mut rank = vec![1.0/nodes as f64;nodes];
+		for _ in 0..self.max_iterations{
+			let share:Vec<f64> = rank.iter().zip(out.iter())
+				.map(|(rank,out)| if *out > 0.0 {rank/out} else {0.0})
+				.collect();
+			let mut next = tree.spmv_transpose(&share);
+			let lost:f64 = rank.iter().zip(out.iter()).filter(|(_,out)| **out <= 0.0).map(|(rank,_)| rank).sum();
+			for (node,value) in next.iter_mut().enumerate(){
+				*value = self.damping * *value + teleport;
+				match self.dangling{
+					Dangling::Uniform => *value+=self.damping*lost/nodes as f64,
+					Dangling::SelfLoop => if out[node] <= 0.0 {*value+=self.damping*rank[node]}
+				}
+			}
+			let change:f64 = next.iter().zip(rank.iter()).map(|(a,b)| (a-b).abs()).sum();
+			rank = next;
+			if change < self.tolerance{
+				break;
+			}
+		}
+		rank
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::k2tree::K2tree;
+	use super::{PageRank,Dangling};
+
+	fn close(a:&[f64],b:&[f64])->bool{
+		a.iter().zip(b.iter()).all(|(a,b)| (a-b).abs() < 1e-6)
+	}
+
+	#[test]
+	fn cycle_is_uniform(){
+		let cycle = K2tree::from_triplets(4,4,2,false,(0..4).map(|i| (i,(i+1)%4,true)));
+		assert!(close(&PageRank::new().run(&cycle),&[0.25;4]));
+	}
+
+	#[test]
+	fn dangling_nodes(){
+		//0->1, 0->2, 1->2, 2 has no arcs
+		let graph = K2tree::from_triplets(3,3,2,false,vec![(0,1,true),(0,2,true),(1,2,true)]);
+		let uniform = PageRank::new().run(&graph);
+		assert!((uniform.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+		assert!(uniform[2] > uniform[1] && uniform[1] > uniform[0]);
+		//Fixed point of the uniform model, solved by hand
+		assert!(close(&uniform,&[0.1975796,0.2815510,0.5208694]));
+
+		let self_loop = PageRank::new().dangling(Dangling::SelfLoop).run(&graph);
+		assert!((self_loop.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+		assert!(self_loop[2] > uniform[2]);
+	}
+
+	#[test]
+	fn weights_split_rank(){
+		let graph = K2tree::from_triplets(3,3,2,0u32,vec![(0,0,4),(0,1,3),(0,2,1),(1,0,1),(2,0,1)]);
+		let rank = PageRank::new().damping(1.0).tolerance(1e-12).max_iterations(1000).run(&graph);
+		assert!(close(&rank,&[2.0/3.0,0.25,1.0/12.0]));
+	}
+}
@@ -0,0 +1,147 @@
+use super::{neighbours,Direction};
+use crate::k2tree::K2tree;
+
+//Arcs count as undirected edges (row and column neighbours merged), self loops are ignored
+
+fn undirected_neighbours<T>(tree:&K2tree<T>,node:usize)->Vec<usize> where T:Eq + Clone{
+	let mut both = neighbours(tree,node,Direction::Undirected);
+	both.retain(|&other| other != node);
+	both
+}
+
+//Undirected neighbours of node greater than it, from the part of its row and column above it
+fn neighbours_above<T>(tree:&K2tree<T>,node:usize)->Vec<usize> where T:Eq + Clone{
+	let last = tree.get_rows()-1;
+	let mut above:Vec<usize> = tree.range(node..=node,node+1..=last).into_iter().map(|((_,j),_)| j)
+		.chain(tree.range(node+1..=last,node..=node).into_iter().map(|((i,_),_)| i))
+		.collect();
+	above.sort_unstable();
+	above.dedup();
+	above
+}
+
+//Elements of both sorted lists greater than bound
+fn common_above(a:&[usize],b:&[usize],bound:usize)->Vec<usize>{
+	let mut common = Vec::new();
+	let (mut x,mut y) = (a.partition_point(|&e| e <= bound),b.partition_point(|&e| e <= bound));
+	while x < a.len() && y < b.len(){
+		match a[x].cmp(&b[y]){
+			std::cmp::Ordering::Less => x+=1,
+			std::cmp::Ordering::Greater => y+=1,
+			std::cmp::Ordering::Equal => {
+				common.push(a[x]);
+				x+=1;
+				y+=1;
+			}
+		}
+	}
+	common
+}
+
+//(triangles,degree) of each node. Every triangle u<v<w is found once, from the edge (u,v),
+//intersecting the neighbourhood of u with the part of the neighbourhood of v above v. Only
+//the neighbourhood of the current u is kept, the rest is queried from the tree.
+fn count_triangles<T>(tree:&K2tree<T>)->(Vec<usize>,Vec<usize>) where T:Eq + Clone{
+	assert!(tree.get_rows() == tree.get_cols(),"graph must be square");
+	let mut triangles = vec![0;tree.get_rows()];
+	let mut degrees = vec![0;tree.get_rows()];
+	for u in 0..tree.get_rows(){
+		let around = undirected_neighbours(tree,u);
+		degrees[u] = around.len();
+		for &v in around.iter().filter(|&&v| v > u){
+			for w in common_above(&around,&neighbours_above(tree,v),v){
+				triangles[u]+=1;
+				triangles[v]+=1;
+				triangles[w]+=1;
+			}
+		}
+	}
+	(triangles,degrees)
+}
+
+//Triangles each node belongs to
+pub fn triangles_per_node<T>(tree:&K2tree<T>)->Vec<usize> where T:Eq + Clone{
+	count_triangles(tree).0
+}
+
+pub fn triangle_count<T>(tree:&K2tree<T>)->usize where T:Eq + Clone{
+	triangles_per_node(tree).iter().sum::<usize>()/3
+}
+
+//Triangles of each node over the pairs of its neighbours, 0 with less than two neighbours
+pub fn local_clustering<T>(tree:&K2tree<T>)->Vec<f64> where T:Eq + Clone{
+	let (triangles,degrees) = count_triangles(tree);
+	triangles.into_iter().zip(degrees).map(|(triangles,degree)| {
+		if degree < 2 {0.0} else {triangles as f64/(degree*(degree-1)/2) as f64}
+	}).collect()
+}
+
+//Closed triplets over all connected triplets (transitivity), 0 without any triplet
+pub fn global_clustering<T>(tree:&K2tree<T>)->f64 where T:Eq + Clone{
+	let (triangles,degrees) = count_triangles(tree);
+	let triplets:usize = degrees.iter().map(|degree| degree*degree.saturating_sub(1)/2).sum();
+	if triplets == 0{
+		return 0.0;
+	}
+	let closed:usize = triangles.iter().sum();
+	closed as f64/triplets as f64
+}
+
+#[cfg(test)]
+mod tests{
+	use rand::Rng;
+	use crate::k2tree::K2tree;
+	use super::{triangles_per_node,triangle_count,local_clustering,global_clustering};
+
+	fn graph(nodes:usize,arcs:&[(usize,usize)])->K2tree<bool>{
+		K2tree::from_triplets(nodes,nodes,2,false,arcs.iter().map(|&(i,j)| (i,j,true)))
+	}
+
+	#[test]
+	fn triangles(){
+		//Two triangles sharing the edge 1-2, arcs in both directions and a self loop
+		let k2tree = graph(5,&[(0,1),(1,2),(2,0),(1,3),(3,2),(2,1),(4,4)]);
+		assert_eq!(triangles_per_node(&k2tree),vec![1,2,2,1,0]);
+		assert_eq!(triangle_count(&k2tree),2);
+		let complete = graph(5,&(0..5).flat_map(|i| (0..5).map(move |j| (i,j))).collect::<Vec<_>>());
+		assert_eq!(triangle_count(&complete),10);
+		let symmetric = K2tree::from_triplets_symmetric(4,2,false,vec![(0,1,true),(1,2,true),(0,2,true)]);
+		assert_eq!(triangles_per_node(&symmetric),vec![1,1,1,0]);
+	}
+
+	#[test]
+	fn clustering(){
+		let k2tree = graph(5,&[(0,1),(1,2),(2,0),(1,3),(3,2)]);
+		let local = local_clustering(&k2tree);
+		assert_eq!(local[0],1.0);
+		assert!((local[1] - 2.0/3.0).abs() < 1e-12);
+		assert_eq!(local[4],0.0);
+		//6 closed triplets out of 1+3+3+1 connected ones
+		assert!((global_clustering(&k2tree) - 6.0/8.0).abs() < 1e-12);
+		assert_eq!(global_clustering(&graph(3,&[(0,1)])),0.0);
+	}
+
+	#[test]
+	fn random_graphs(){
+		let mut rng = rand::thread_rng();
+		for nodes in [2,9,17]{
+			let arcs:Vec<(usize,usize)> = (0..nodes*3).map(|_| (rng.gen_range(0..nodes),rng.gen_range(0..nodes))).collect();
+			let linked = |a:usize,b:usize| a != b && (arcs.contains(&(a,b)) || arcs.contains(&(b,a)));
+			let mut expected = vec![0;nodes];
+			for u in 0..nodes{
+				for v in u+1..nodes{
+					for w in v+1..nodes{
+						if linked(u,v) && linked(v,w) && linked(u,w){
+							expected[u]+=1;
+							expected[v]+=1;
+							expected[w]+=1;
+						}
+					}
+				}
+			}
+			assert_eq!(triangles_per_node(&graph(nodes,&arcs)),expected);
+			let symmetric = K2tree::from_triplets_symmetric(nodes,2,false,arcs.iter().map(|&(i,j)| (i,j,true)));
+			assert_eq!(triangles_per_node(&symmetric),expected);
+		}
+	}
+}
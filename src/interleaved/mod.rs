@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+
+use serde::{Serialize, Deserialize};
+
+use crate::k2tree::{K2tree,next_pow};
+use crate::sequence::Sequence;
+
+//Interleaved k2-tree (ik2-tree): the binary relations over the same n nodes share one tree.
+//Every cell holds one bit per relation still present in its parent, so a cell of the first
+//level has one bit per relation and deeper cells only carry the relations that reach them.
+//The children of a cell with c set bits take k^2 cells of c bits each, and they come after
+//the children of every set bit before it, so they start at k^2*(relations + set bits before).
+#[derive(Serialize,Deserialize,Debug)]
+pub struct InterleavedK2tree{
+	nodes:usize,
+	relations:usize,
+	virtual_size:usize,
+	k:usize,
+	bits:Sequence<bool>
+}
+
+//(relation, index of its bit inside the cells of the current node)
+type Active = Vec<(usize,usize)>;
+
+impl InterleavedK2tree{
+	//(relation,i,j) triplets, repeated ones are ignored
+	pub fn from_triplets(nodes:usize,relations:usize,k:usize,triplets:impl IntoIterator<Item=(usize,usize,usize)>)->Self{
+		assert!(nodes>0 && relations>0);
+		assert!(k>1,"k must be greater than 1");
+		let mut points:Vec<(usize,usize,usize)> = triplets.into_iter().inspect(|&(r,i,j)| {
+			assert!(r<relations,"relation overflows");
+			assert!(i<nodes && j<nodes,"position overflows");
+		}).collect();
+		points.sort_unstable();
+		points.dedup();
+		let mut tree = InterleavedK2tree{
+			nodes,
+			relations,
+			virtual_size:std::cmp::max(k,next_pow(k,nodes)),
+			k,
+			bits:Sequence::new(true)
+		};
+		tree.build(points);
+		tree
+	}
+
+	//One relation per tree, all of them square with the same side
+	pub fn from_trees(trees:&[K2tree<bool>],k:usize)->Self{
+		assert!(!trees.is_empty(),"no trees");
+		let nodes = trees[0].get_rows();
+		assert!(trees.iter().all(|tree| tree.get_rows() == nodes && tree.get_cols() == nodes),"trees must be square with the same side");
+		let triplets = trees.iter().enumerate().flat_map(|(r,tree)| {
+			tree.range(0..=nodes-1,0..=nodes-1).into_iter().map(move |((i,j),_)| (r,i,j))
+		});
+		Self::from_triplets(nodes,trees.len(),k,triplets.collect::<Vec<_>>())
+	}
+
+	fn build(&mut self,points:Vec<(usize,usize,usize)>){
+		let k = self.k;
+		//(row offset, column offset, side, relations present, cells inside)
+		let mut target = VecDeque::new();
+		target.push_back((0,0,self.virtual_size,(0..self.relations).collect::<Vec<usize>>(),points));
+		while let Some((row_off,col_off,size,present,points)) = target.pop_front(){
+			let sub = size/k;
+			let mut buckets:Vec<Vec<(usize,usize,usize)>> = (0..k*k).map(|_| Vec::new()).collect();
+			for point in points{
+				buckets[((point.1-row_off)/sub)*k + (point.2-col_off)/sub].push(point);
+			}
+			for (index,bucket) in buckets.into_iter().enumerate(){
+				let mut reached = Vec::new();
+				for &relation in &present{
+					let set = bucket.iter().any(|&(r,_,_)| r == relation);
+					self.bits.push(set);
+					if set{
+						reached.push(relation);
+					}
+				}
+				if sub > 1 && !reached.is_empty(){
+					target.push_back((row_off + (index/k)*sub,col_off + (index%k)*sub,sub,reached,bucket));
+				}
+			}
+		}
+	}
+
+	fn ones_before(&self,pos:usize)->usize{
+		if pos == 0 {0} else {self.bits.rank(pos-1).unwrap()}
+	}
+
+	fn is_set(&self,pos:usize)->bool{
+		*self.bits.get(pos).unwrap()
+	}
+
+	//Calls f(i,j,relation) for the cells of the window set in the given relations
+	fn walk<F>(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>,active:Active,f:&mut F) where F:FnMut(usize,usize,usize){
+		let rows = *rows.start()..=std::cmp::min(*rows.end(),self.nodes-1);
+		let cols = *cols.start()..=std::cmp::min(*cols.end(),self.nodes-1);
+		if rows.is_empty() || cols.is_empty() || active.is_empty(){
+			return;
+		}
+		self.walk_node(0,self.relations,self.virtual_size,(0,0),&rows,&cols,&active,f);
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn walk_node<F>(&self,start:usize,width:usize,size:usize,offset:(usize,usize),
+		rows:&RangeInclusive<usize>,cols:&RangeInclusive<usize>,active:&Active,f:&mut F) where F:FnMut(usize,usize,usize){
+		let sub = size/self.k;
+		for y in 0..self.k{
+			let row = offset.0 + y*sub;
+			if row+sub-1 < *rows.start() || row > *rows.end(){
+				continue;
+			}
+			for x in 0..self.k{
+				let col = offset.1 + x*sub;
+				if col+sub-1 < *cols.start() || col > *cols.end(){
+					continue;
+				}
+				let cell = start + (y*self.k + x)*width;
+				let below = self.ones_before(cell);
+				let reached:Active = active.iter()
+					.filter(|&&(_,bit)| self.is_set(cell+bit))
+					.map(|&(relation,bit)| (relation,self.ones_before(cell+bit)-below))
+					.collect();
+				if reached.is_empty(){
+					continue;
+				}
+				if sub == 1{
+					for &(relation,_) in &reached{
+						f(row,col,relation);
+					}
+					continue;
+				}
+				let children = self.k*self.k*(self.relations + below);
+				let set = self.ones_before(cell+width) - below;
+				self.walk_node(children,set,sub,(row,col),rows,cols,&reached,f);
+			}
+		}
+	}
+
+	fn one(&self,relation:usize)->Active{
+		assert!(relation<self.relations,"relation overflows");
+		vec![(relation,relation)]
+	}
+
+	fn all(&self)->Active{
+		(0..self.relations).map(|relation| (relation,relation)).collect()
+	}
+
+	pub fn get(&self,relation:usize,i:usize,j:usize)->bool{
+		assert!(i<self.nodes && j<self.nodes,"position overflows");
+		let mut found = false;
+		self.walk(i..=i,j..=j,self.one(relation),&mut |_,_,_| found = true);
+		found
+	}
+
+	//Successors of i in the relation, sorted
+	pub fn row(&self,relation:usize,i:usize)->Vec<usize>{
+		let mut output = Vec::new();
+		self.walk(i..=i,0..=self.nodes-1,self.one(relation),&mut |_,j,_| output.push(j));
+		output
+	}
+
+	//Predecessors of j in the relation, sorted
+	pub fn column(&self,relation:usize,j:usize)->Vec<usize>{
+		let mut output = Vec::new();
+		self.walk(0..=self.nodes-1,j..=j,self.one(relation),&mut |i,_,_| output.push(i));
+		output
+	}
+
+	pub fn range(&self,relation:usize,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->Vec<(usize,usize)>{
+		let mut output = Vec::new();
+		self.walk(rows,cols,self.one(relation),&mut |i,j,_| output.push((i,j)));
+		output
+	}
+
+	//Relations that connect i to j, sorted
+	pub fn relations(&self,i:usize,j:usize)->Vec<usize>{
+		assert!(i<self.nodes && j<self.nodes,"position overflows");
+		let mut output = Vec::new();
+		self.walk(i..=i,j..=j,self.all(),&mut |_,_,relation| output.push(relation));
+		output
+	}
+
+	//(successor, relation) pairs of i over every relation, sorted
+	pub fn row_all(&self,i:usize)->Vec<(usize,usize)>{
+		let mut output = Vec::new();
+		self.walk(i..=i,0..=self.nodes-1,self.all(),&mut |_,j,relation| output.push((j,relation)));
+		output
+	}
+
+	//(predecessor, relation) pairs of j over every relation, sorted
+	pub fn column_all(&self,j:usize)->Vec<(usize,usize)>{
+		let mut output = Vec::new();
+		self.walk(0..=self.nodes-1,j..=j,self.all(),&mut |i,_,relation| output.push((i,relation)));
+		output
+	}
+
+	//(i,j,relation) of every set cell of the window
+	pub fn range_all(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->Vec<(usize,usize,usize)>{
+		let mut output = Vec::new();
+		self.walk(rows,cols,self.all(),&mut |i,j,relation| output.push((i,j,relation)));
+		output
+	}
+
+	pub fn get_bits(&self)->&Sequence<bool>{
+		&self.bits
+	}
+	pub fn get_nodes(&self)->usize{
+		self.nodes
+	}
+	pub fn get_relations(&self)->usize{
+		self.relations
+	}
+	pub fn get_k(&self)->usize{
+		self.k
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use std::collections::BTreeSet;
+	use rand::Rng;
+	use crate::k2tree::K2tree;
+	use super::InterleavedK2tree;
+
+	fn random_triplets(nodes:usize,relations:usize,count:usize)->BTreeSet<(usize,usize,usize)>{
+		let mut rng = rand::thread_rng();
+		(0..count).map(|_| (rng.gen_range(0..relations),rng.gen_range(0..nodes),rng.gen_range(0..nodes))).collect()
+	}
+
+	#[test]
+	fn fixed_relation_queries(){
+		for (nodes,relations,k) in [(1,1,2),(10,3,2),(37,8,2),(20,5,3)]{
+			let triplets = random_triplets(nodes,relations,nodes*relations);
+			let tree = InterleavedK2tree::from_triplets(nodes,relations,k,triplets.iter().copied());
+			for r in 0..relations{
+				for i in 0..nodes{
+					let expected:Vec<usize> = (0..nodes).filter(|&j| triplets.contains(&(r,i,j))).collect();
+					assert_eq!(tree.row(r,i),expected);
+					let expected:Vec<usize> = (0..nodes).filter(|&j| triplets.contains(&(r,j,i))).collect();
+					assert_eq!(tree.column(r,i),expected);
+					for j in 0..nodes{
+						assert_eq!(tree.get(r,i,j),triplets.contains(&(r,i,j)));
+					}
+				}
+				assert_eq!(tree.range(r,0..=nodes-1,0..=nodes-1).len(),triplets.iter().filter(|t| t.0 == r).count());
+			}
+		}
+	}
+
+	#[test]
+	fn all_relation_queries(){
+		let triplets = random_triplets(24,6,150);
+		let tree = InterleavedK2tree::from_triplets(24,6,2,triplets.iter().copied());
+		for i in 0..24{
+			for j in 0..24{
+				let expected:Vec<usize> = (0..6).filter(|&r| triplets.contains(&(r,i,j))).collect();
+				assert_eq!(tree.relations(i,j),expected);
+			}
+			let expected:Vec<(usize,usize)> = triplets.iter().filter(|t| t.1 == i).map(|t| (t.2,t.0)).collect::<BTreeSet<_>>().into_iter().collect();
+			assert_eq!(tree.row_all(i),expected);
+			let expected:Vec<(usize,usize)> = triplets.iter().filter(|t| t.2 == i).map(|t| (t.1,t.0)).collect::<BTreeSet<_>>().into_iter().collect();
+			assert_eq!(tree.column_all(i),expected);
+		}
+		assert_eq!(tree.range_all(0..=23,0..=23).len(),triplets.len());
+	}
+
+	#[test]
+	fn from_trees_shares_levels(){
+		let knows = K2tree::from_triplets(8,8,2,false,vec![(0,1,true),(7,7,true)]);
+		let likes = K2tree::from_triplets(8,8,2,false,vec![(0,1,true),(3,4,true)]);
+		let tree = InterleavedK2tree::from_trees(&[knows,likes],2);
+		assert_eq!(tree.relations(0,1),vec![0,1]);
+		assert_eq!(tree.relations(3,4),vec![1]);
+		assert!(tree.relations(4,3).is_empty());
+		assert_eq!(tree.row_all(0),vec![(1,0),(1,1)]);
+		//Cells empty for both relations have no children
+		assert_eq!(tree.get_bits().len(),4*2 + 4*(2+1+1) + 4*(2+1+1));
+	}
+}
@@ -0,0 +1,161 @@
+use std::cmp::Ordering;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::K2tree;
+
+impl <T> K2tree<T> where T:Eq + Clone{
+
+	//Child index (y*k+x) of the cell at the given level, the root children are level 1
+	fn digit(&self,i:usize,j:usize,level:usize)->usize{
+		let elems = self.virtual_rows/self.k.pow(level as u32);
+		((i/elems)%self.k)*self.k + (j/elems)%self.k
+	}
+
+	//Z-order over k^2 children: cells that share a path from the root end up together
+	fn morton_cmp(&self,a:(usize,usize),b:(usize,usize))->Ordering{
+		let mut level = 1;
+		let mut elems = self.virtual_rows/self.k;
+		while elems > 0{
+			let ordering = self.digit(a.0,a.1,level).cmp(&self.digit(b.0,b.1,level));
+			if ordering != Ordering::Equal{
+				return ordering;
+			}
+			level+=1;
+			elems/=self.k;
+		}
+		Ordering::Equal
+	}
+
+	//Queries checked and moved to the stored triangle, plus their indices (unsorted)
+	fn batch_points(&self,queries:&[(usize,usize)])->(Vec<(usize,usize)>,Vec<usize>){
+		let points = queries.iter().map(|&(i,j)| {
+			assert!(i<self.get_rows() && j<self.get_cols(),"position overflows k2tree");
+			if self.symmetric && i > j {(j,i)} else {(i,j)}
+		}).collect();
+		(points,(0..queries.len()).collect())
+	}
+
+	//Looks up points in the given order. Each walk starts from the deepest node it shares
+	//with the previous one instead of from the root.
+	fn lookup_sorted<'a,F>(&'a self,points:&[(usize,usize)],order:&[usize],f:&mut F)
+	where F:FnMut(usize,Option<&'a T>){
+		//Child indices of the last walk and the first child position below each of them
+		let mut path:Vec<usize> = Vec::new();
+		let mut bases:Vec<usize> = vec![0];
+		let mut last:Option<&'a T> = None;
+		for &index in order{
+			let (i,j) = points[index];
+			let mut level = 0;
+			while level < path.len() && self.digit(i,j,level+1) == path[level]{
+				level+=1;
+			}
+			if !path.is_empty() && level == path.len(){
+				f(index,last);
+				continue;
+			}
+			path.truncate(level);
+			bases.truncate(level+1);
+			loop{
+				let digit = self.digit(i,j,level+1);
+				let pos = bases[level] + digit;
+				path.push(digit);
+				if pos >= self.nodes.len(){
+					last = self.leaf.get(pos-self.nodes.len());
+					break;
+				}
+				match self.nodes.get(pos).unwrap(){
+					None => {
+						bases.push(self.first_child(Some(pos)));
+						level+=1;
+					},
+					Some(value) => {
+						last = Some(value);
+						break;
+					}
+				}
+			}
+			f(index,last);
+		}
+	}
+
+	//Same as calling get on every position, results in the order of the queries
+	pub fn get_many(&self,queries:&[(usize,usize)])->Vec<Option<&T>>{
+		let (points,mut order) = self.batch_points(queries);
+		order.sort_by(|&a,&b| self.morton_cmp(points[a],points[b]));
+		let mut results = vec![None;queries.len()];
+		self.lookup_sorted(&points,&order,&mut |index,value| results[index] = value);
+		results
+	}
+}
+
+#[cfg(feature = "parallel")]
+impl <T> K2tree<T> where T:Eq + Clone + Sync{
+
+	//get_many with the sort and the lookups spread over the rayon pool. Every thread walks
+	//a contiguous run of the sorted queries.
+	pub fn get_many_parallel(&self,queries:&[(usize,usize)])->Vec<Option<&T>>{
+		let (points,mut order) = self.batch_points(queries);
+		order.par_sort_by(|&a,&b| self.morton_cmp(points[a],points[b]));
+		let chunk = std::cmp::max(1,order.len()/(4*rayon::current_num_threads()));
+		let found:Vec<Vec<(usize,Option<&T>)>> = order.par_chunks(chunk).map(|chunk| {
+			let mut found = Vec::with_capacity(chunk.len());
+			self.lookup_sorted(&points,chunk,&mut |index,value| found.push((index,value)));
+			found
+		}).collect();
+		let mut results = vec![None;queries.len()];
+		for (index,value) in found.into_iter().flatten(){
+			results[index] = value;
+		}
+		results
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use rand::Rng;
+	use crate::k2tree::K2tree;
+
+	fn random_queries(rows:usize,cols:usize,count:usize)->Vec<(usize,usize)>{
+		let mut rng = rand::thread_rng();
+		(0..count).map(|_| (rng.gen_range(0..rows),rng.gen_range(0..cols))).collect()
+	}
+
+	fn random_tree(rows:usize,cols:usize,k:usize)->K2tree<u8>{
+		let mut rng = rand::thread_rng();
+		let cells:Vec<(usize,usize,u8)> = (0..rows*cols/8).map(|_| (rng.gen_range(0..rows),rng.gen_range(0..cols),rng.gen_range(1..4))).collect();
+		//A uniform block, so some walks stop above the leaves
+		let block = (0..rows.min(4)).flat_map(|i| (0..cols.min(4)).map(move |j| (i,j,7)));
+		K2tree::from_triplets(rows,cols,k,0,cells.into_iter().chain(block))
+	}
+
+	#[test]
+	fn get_many_matches_get(){
+		for (rows,cols,k) in [(1,1,2),(5,9,2),(40,40,2),(27,20,3)]{
+			let k2tree = random_tree(rows,cols,k);
+			let mut queries = random_queries(rows,cols,500);
+			queries.push(queries[0]);
+			let expected:Vec<Option<&u8>> = queries.iter().map(|&(i,j)| k2tree.get(i,j)).collect();
+			assert_eq!(k2tree.get_many(&queries),expected);
+		}
+		assert!(random_tree(4,4,2).get_many(&[]).is_empty());
+	}
+
+	#[test]
+	fn get_many_symmetric(){
+		let k2tree = K2tree::from_triplets_symmetric(12,2,0u8,vec![(0,5,1),(7,2,2),(11,11,3)]);
+		let queries = random_queries(12,12,300);
+		let expected:Vec<Option<&u8>> = queries.iter().map(|&(i,j)| k2tree.get(i,j)).collect();
+		assert_eq!(k2tree.get_many(&queries),expected);
+		assert_eq!(k2tree.get_many(&[(5,0),(2,7)]),vec![Some(&1),Some(&2)]);
+	}
+
+	#[cfg(feature = "parallel")]
+	#[test]
+	fn get_many_parallel_matches_get(){
+		let k2tree = random_tree(64,50,2);
+		let queries = random_queries(64,50,5000);
+		assert_eq!(k2tree.get_many_parallel(&queries),k2tree.get_many(&queries));
+	}
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use rand::{Rng,SeedableRng};
+use rand::rngs::StdRng;
+
+use super::K2tree;
+use crate::matrix::Matrix;
+
+//How the builder picks the value that fills the uniform regions of the tree
+#[derive(Debug,Clone,PartialEq)]
+pub enum Background<T>{
+	//Keep the background of the input matrix
+	Matrix,
+	Fixed(T),
+	//Most frequent value among the cells of the input, padding left out
+	MostFrequent,
+	//Most frequent value among this many cells of the input drawn at random (must be greater
+	//than 0, checked by K2treeBuilder::background). The same seed always picks the same cells.
+	Sampled{cells:usize,seed:u64}
+}
+
+#[derive(Debug,Clone)]
+pub struct BuildStats<T>{
+	pub background:T,
+	//Occurrences of the background among the scanned cells (0 if nothing was scanned)
+	pub background_count:usize,
+	pub scanned:usize,
+	pub nodes:usize,
+	pub leaves:usize,
+	pub build_time:Duration
+}
+
+#[derive(Debug,Clone)]
+pub struct K2treeBuilder<T>{
+	k:usize,
+	background:Background<T>,
+	symmetric:bool,
+	counts:bool
+}
+
+impl <T> Default for K2treeBuilder<T>{
+	fn default()->Self{
+		K2treeBuilder{
+			k:2,
+			background:Background::Matrix,
+			symmetric:false,
+			counts:false
+		}
+	}
+}
+
+impl <T> K2treeBuilder<T> where T:Eq + Clone + Hash{
+	pub fn new()->Self{
+		Self::default()
+	}
+	pub fn k(mut self,k:usize)->Self{
+		assert!(k>1,"k must be greater than 1");
+		self.k = k;
+		self
+	}
+	pub fn background(mut self,background:Background<T>)->Self{
+		assert!(!matches!(background,Background::Sampled{cells:0,..}),"sampled background needs at least one cell");
+		self.background = background;
+		self
+	}
+	//Keep only the upper triangle of a square matrix, queries mirror it into the lower one.
+	//Building panics if the matrix is not symmetric.
+	pub fn symmetric(mut self,symmetric:bool)->Self{
+		self.symmetric = symmetric;
+		self
+	}
+	//Store the cell counts of internal nodes, for count_in_range and sampling
+	pub fn counts(mut self,counts:bool)->Self{
+		self.counts = counts;
+		self
+	}
+	pub fn build(&self,matrix:Matrix<T>)->K2tree<T>{
+		self.build_with_stats(matrix).0
+	}
+	pub fn build_with_stats(&self,mut matrix:Matrix<T>)->(K2tree<T>,BuildStats<T>){
+		let start = Instant::now();
+		let matrix_background = || (matrix.get_background().clone(),0,0);
+		let (background,background_count,scanned) = match &self.background{
+			Background::Matrix => matrix_background(),
+			Background::Fixed(value) => (value.clone(),0,0),
+			//Only the stored cells vote, the padding takes whatever background is chosen
+			Background::MostFrequent => most_frequent(matrix.get_inner().iter()).unwrap_or_else(matrix_background),
+			Background::Sampled{cells,seed} => {
+				let mut rng = StdRng::seed_from_u64(*seed);
+				let (rows,cols) = (matrix.get_orig_rows(),matrix.get_orig_cols());
+				let sample = (0..*cells).map(|_| matrix.get(rng.gen_range(0..rows),rng.gen_range(0..cols)).unwrap());
+				most_frequent(sample).unwrap_or_else(matrix_background)
+			}
+		};
+		matrix.set_background(background.clone());
+		if self.symmetric{
+			assert_eq!(matrix.get_rows(),matrix.get_cols(),"symmetric matrix must be square");
+			//Padding reads as background, so a real cell can also be mirrored by a padded one
+			for i in 0..matrix.get_rows(){
+				for j in 0..i{
+					assert!(matrix.get(i,j) == matrix.get(j,i),"matrix is not symmetric: ({},{}) differs from ({},{})",i,j,j,i);
+					if i < matrix.get_orig_rows() && j < matrix.get_orig_cols(){
+						matrix.set(i,j,background.clone());
+					}
+				}
+			}
+		}
+		let mut tree = K2tree::new(matrix,self.k);
+		tree.symmetric = self.symmetric;
+		if self.counts{
+			tree.store_counts();
+		}
+		let stats = BuildStats{
+			background,
+			background_count,
+			scanned,
+			nodes:tree.get_nodes().len(),
+			leaves:tree.get_leaf().len(),
+			build_time:start.elapsed()
+		};
+		(tree,stats)
+	}
+}
+
+//(value,occurrences,scanned), None without values. Ties go to the value seen first.
+fn most_frequent<'a,T>(values:impl Iterator<Item=&'a T>)->Option<(T,usize,usize)> where T:'a + Eq + Clone + Hash{
+	let mut index:HashMap<&T,usize> = HashMap::new();
+	let mut counts:Vec<(&T,usize)> = Vec::new();
+	let mut scanned = 0;
+	for value in values{
+		scanned+=1;
+		match index.get(value){
+			Some(&e) => counts[e].1+=1,
+			None => {
+				index.insert(value,counts.len());
+				counts.push((value,1));
+			}
+		}
+	}
+	if scanned == 0{
+		return None;
+	}
+	let mut best = 0;
+	for (e,(_,count)) in counts.iter().enumerate(){
+		if *count > counts[best].1{
+			best = e;
+		}
+	}
+	Some((counts[best].0.clone(),counts[best].1,scanned))
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::matrix::Matrix;
+	use crate::k2tree::K2tree;
+	use super::{K2treeBuilder,Background};
+
+	fn mostly_fives()->Matrix<usize>{
+		let size = 16;
+		let mut matrix = Matrix::from_iter(size,size,(0..size*size).map(|_| 5));
+		matrix.set(0,0,0);
+		matrix.set(3,9,1);
+		matrix.set(12,4,0);
+		matrix
+	}
+
+	#[test]
+	fn most_frequent_background(){
+		let matrix = mostly_fives();
+		let (k2tree,stats) = K2treeBuilder::new().background(Background::MostFrequent).build_with_stats(matrix.clone());
+		assert_eq!(*k2tree.get_background(),5);
+		assert_eq!(stats.background,5);
+		assert_eq!(stats.background_count,253);
+		assert_eq!(stats.scanned,256);
+		assert_eq!(k2tree.range(0..=15,0..=15).len(),3);
+		for i in 0..16{
+			for j in 0..16{
+				assert_eq!(k2tree.get(i,j),matrix.get(i,j));
+			}
+		}
+		//Same layout as building with the value given explicitly
+		let fixed = K2treeBuilder::new().background(Background::Fixed(5)).build(matrix);
+		assert_eq!(fixed.get_nodes().get_data(),k2tree.get_nodes().get_data());
+	}
+
+	#[test]
+	fn sampled_background(){
+		let matrix = mostly_fives();
+		let builder = K2treeBuilder::new().k(4).background(Background::Sampled{cells:100,seed:17});
+		let (k2tree,stats) = builder.build_with_stats(matrix.clone());
+		assert_eq!(stats.scanned,100);
+		assert_eq!(stats.background,5);
+		assert_eq!(k2tree.get_k(),4);
+		//Same seed, same sample
+		let (_,again) = builder.build_with_stats(matrix);
+		assert_eq!(again.background_count,stats.background_count);
+	}
+
+	#[test]
+	fn padding_does_not_vote(){
+		//12 real zeros against 13 real ones, padded to 8x8 with zeros
+		let matrix = Matrix::from_iter(5,5,(0..25).map(|e| if e < 13 {1} else {0})).expand(8,8);
+		let (k2tree,stats) = K2treeBuilder::new().background(Background::MostFrequent).build_with_stats(matrix.clone());
+		assert_eq!(stats.background,1);
+		assert_eq!(stats.scanned,25);
+		assert_eq!(stats.background_count,13);
+		for i in 0..5{
+			for j in 0..5{
+				assert_eq!(k2tree.get(i,j),matrix.get(i,j));
+			}
+		}
+	}
+
+	#[test]
+	fn symmetric_build(){
+		let mut matrix = Matrix::new(5,5);
+		for (i,j) in [(0,3),(1,1),(2,4),(4,0)]{
+			matrix.set(i,j,1);
+			matrix.set(j,i,1);
+		}
+		let k2tree = K2treeBuilder::new().symmetric(true).build(matrix.clone());
+		assert!(k2tree.is_symmetric());
+		assert!(k2tree.get_leaf().len() < K2tree::new(matrix.clone(),2).get_leaf().len());
+		for i in 0..5{
+			for j in 0..5{
+				assert_eq!(k2tree.get(i,j),matrix.get(i,j));
+			}
+		}
+		assert_eq!(k2tree.row(4),vec![(0,&1),(2,&1)]);
+		assert_eq!(k2tree.column(3),vec![(0,&1)]);
+	}
+
+	#[test]
+	#[should_panic(expected = "sampled background needs at least one cell")]
+	fn empty_sample_is_rejected(){
+		let _ = K2treeBuilder::<usize>::new().background(Background::Sampled{cells:0,seed:1});
+	}
+
+	#[test]
+	#[should_panic(expected = "matrix is not symmetric: (4,1) differs from (1,4)")]
+	fn symmetric_build_rejects_asymmetric(){
+		let mut matrix = Matrix::new(5,5);
+		matrix.set(0,3,1);
+		matrix.set(3,0,1);
+		matrix.set(4,1,1);
+		K2treeBuilder::new().symmetric(true).build(matrix);
+	}
+
+	#[test]
+	#[should_panic(expected = "matrix is not symmetric")]
+	fn symmetric_build_checks_padding(){
+		//(0,2) is real but its mirror (2,0) is padding
+		let mut matrix = Matrix::new(2,3).expand(3,3);
+		matrix.set(0,2,1);
+		K2treeBuilder::new().symmetric(true).build(matrix);
+	}
+
+	#[test]
+	fn matrix_background_is_default(){
+		let matrix = mostly_fives();
+		let (k2tree,stats) = K2treeBuilder::new().build_with_stats(matrix.clone());
+		assert_eq!(stats.background,0);
+		assert_eq!(stats.scanned,0);
+		assert_eq!(k2tree.get_nodes().get_data(),K2tree::new(matrix,2).get_nodes().get_data());
+	}
+}
@@ -0,0 +1,207 @@
+use std::ops::RangeInclusive;
+
+use super::K2tree;
+
+impl <T> K2tree<T> where T:Eq + Clone{
+
+	//Stores the number of non-background cells under every internal node, by rank. Uniform
+	//nodes and leaves need no count. Symmetric trees count the stored triangle only.
+	pub fn store_counts(&mut self){
+		let internal = self.nodes.iter().filter(|node| node.is_none()).count();
+		let mut counts = vec![0;internal];
+		if self.rows > 0 && self.columns > 0{
+			self.count_subtree(None,self.virtual_rows,(0,0),&mut counts);
+		}
+		self.counts = Some(counts);
+	}
+
+	pub fn drop_counts(&mut self){
+		self.counts = None;
+	}
+
+	pub fn has_counts(&self)->bool{
+		self.counts.is_some()
+	}
+
+	fn count_subtree(&self,pos:Option<usize>,size:usize,offset:(usize,usize),counts:&mut Vec<usize>)->usize{
+		let start = self.first_child(pos);
+		let sub = size/self.k;
+		let mut total = 0;
+		for y in 0..self.k{
+			for x in 0..self.k{
+				let (row,col) = (offset.0 + y*sub,offset.1 + x*sub);
+				let child = start + y*self.k + x;
+				total+=match self.child(child){
+					None => self.count_subtree(Some(child),sub,(row,col),counts),
+					Some(value) => self.uniform_count(value,row..=row+sub-1,col..=col+sub-1)
+				};
+			}
+		}
+		if let Some(pos) = pos{
+			counts[self.nodes.rank(pos).unwrap()-1] = total;
+		}
+		total
+	}
+
+	//Value of a uniform node or leaf, None for internal nodes
+	pub(crate) fn child(&self,pos:usize)->Option<&T>{
+		if pos >= self.nodes.len(){
+			return Some(&self.leaf[pos-self.nodes.len()]);
+		}
+		self.nodes.get(pos).unwrap().as_ref()
+	}
+
+	//Non-background cells of the real area that a uniform block has inside the window
+	pub(crate) fn uniform_count(&self,value:&T,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->usize{
+		if *value == self.background{
+			return 0;
+		}
+		let clip = |range:RangeInclusive<usize>,limit:usize| {
+			let end = std::cmp::min(*range.end(),limit-1);
+			if *range.start() > end {0} else {end-range.start()+1}
+		};
+		clip(rows,self.rows)*clip(cols,self.columns)
+	}
+
+	//Stored cells inside the window, using the counts of internal nodes fully inside it
+	pub(crate) fn count_stored(&self,pos:Option<usize>,size:usize,offset:(usize,usize),
+		rows:&RangeInclusive<usize>,cols:&RangeInclusive<usize>,counts:&[usize])->usize{
+		let start = self.first_child(pos);
+		let sub = size/self.k;
+		let mut total = 0;
+		for y in 0..self.k{
+			let row = offset.0 + y*sub;
+			if row+sub-1 < *rows.start() || row > *rows.end(){
+				continue;
+			}
+			for x in 0..self.k{
+				let col = offset.1 + x*sub;
+				if col+sub-1 < *cols.start() || col > *cols.end(){
+					continue;
+				}
+				let child = start + y*self.k + x;
+				let inside = row >= *rows.start() && row+sub-1 <= *rows.end() && col >= *cols.start() && col+sub-1 <= *cols.end();
+				total+=match self.child(child){
+					None if inside => counts[self.nodes.rank(child).unwrap()-1],
+					None => self.count_stored(Some(child),sub,(row,col),rows,cols,counts),
+					Some(value) => {
+						let block_rows = std::cmp::max(row,*rows.start())..=std::cmp::min(row+sub-1,*rows.end());
+						let block_cols = std::cmp::max(col,*cols.start())..=std::cmp::min(col+sub-1,*cols.end());
+						self.uniform_count(value,block_rows,block_cols)
+					}
+				};
+			}
+		}
+		total
+	}
+
+	//Non-background cells of the window. Without stored counts every cell of the window is
+	//visited, with them whole subtrees inside the window are counted in one step.
+	pub fn count_in_range(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->usize{
+		let counts = match &self.counts{
+			None => {
+				let mut total = 0;
+				self.walk(rows,cols,&self.background,&mut |_,_,_| total+=1);
+				return total;
+			},
+			Some(counts) => counts
+		};
+		if self.rows == 0 || self.columns == 0 || rows.is_empty() || cols.is_empty(){
+			return 0;
+		}
+		let size = self.virtual_rows;
+		let direct = self.count_stored(None,size,(0,0),&rows,&cols,counts);
+		if !self.symmetric{
+			return direct;
+		}
+		//Cells below the diagonal are the stored ones of the transposed window, minus the
+		//diagonal cells it shares with the window
+		let transposed = self.count_stored(None,size,(0,0),&cols,&rows,counts);
+		let first = std::cmp::max(*rows.start(),*cols.start());
+		let last = std::cmp::min(std::cmp::min(*rows.end(),*cols.end()),self.rows-1);
+		let diagonal = (first..=last).filter(|&e| *self.get(e,e).unwrap() != self.background).count();
+		direct + transposed - diagonal
+	}
+
+	pub fn out_degree(&self,i:usize)->usize{
+		assert!(i<self.rows,"row overflows k2tree");
+		self.count_in_range(i..=i,0..=self.columns-1)
+	}
+
+	pub fn in_degree(&self,j:usize)->usize{
+		assert!(j<self.columns,"column overflows k2tree");
+		self.count_in_range(0..=self.rows-1,j..=j)
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use rand::Rng;
+	use crate::matrix::Matrix;
+	use crate::k2tree::{K2tree,K2treeBuilder};
+
+	fn brute_force(k2tree:&K2tree<u8>,rows:std::ops::RangeInclusive<usize>,cols:std::ops::RangeInclusive<usize>)->usize{
+		let mut total = 0;
+		for i in rows.clone(){
+			for j in cols.clone(){
+				if i < k2tree.get_rows() && j < k2tree.get_cols() && *k2tree.get(i,j).unwrap() != 0{
+					total+=1;
+				}
+			}
+		}
+		total
+	}
+
+	fn check_windows(k2tree:&K2tree<u8>){
+		let mut rng = rand::thread_rng();
+		for _ in 0..200{
+			let (a,b) = (rng.gen_range(0..k2tree.get_rows()),rng.gen_range(0..k2tree.get_rows()));
+			let (c,d) = (rng.gen_range(0..k2tree.get_cols()),rng.gen_range(0..k2tree.get_cols()));
+			let rows = std::cmp::min(a,b)..=std::cmp::max(a,b);
+			let cols = std::cmp::min(c,d)..=std::cmp::max(c,d);
+			assert_eq!(k2tree.count_in_range(rows.clone(),cols.clone()),brute_force(k2tree,rows,cols));
+		}
+	}
+
+	#[test]
+	fn counts_match_cells(){
+		let mut rng = rand::thread_rng();
+		let mut matrix = Matrix::new(21,13);
+		for i in 0..8{
+			for j in 0..8{
+				matrix.set(i,j,1);
+			}
+		}
+		for _ in 0..40{
+			matrix.set(rng.gen_range(0..21),rng.gen_range(0..13),rng.gen_range(1..3));
+		}
+		let mut k2tree = K2tree::new(matrix,2);
+		check_windows(&k2tree);
+		k2tree.store_counts();
+		assert!(k2tree.has_counts());
+		check_windows(&k2tree);
+		assert_eq!(k2tree.count_in_range(0..=20,0..=12),brute_force(&k2tree,0..=20,0..=12));
+		assert_eq!(k2tree.out_degree(3),k2tree.row(3).len());
+		assert_eq!(k2tree.in_degree(12),k2tree.column(12).len());
+	}
+
+	#[test]
+	fn symmetric_counts(){
+		let mut k2tree = K2tree::from_triplets_symmetric(10,2,0u8,vec![(0,9,1),(3,3,2),(7,2,1),(5,6,3),(1,8,1)]);
+		k2tree.store_counts();
+		check_windows(&k2tree);
+		for node in 0..10{
+			assert_eq!(k2tree.out_degree(node),k2tree.row(node).len());
+			assert_eq!(k2tree.in_degree(node),k2tree.column(node).len());
+		}
+	}
+
+	#[test]
+	fn builder_stores_counts(){
+		let matrix = Matrix::from_iter(4,4,vec![1,1,0,0, 1,1,0,0, 0,0,0,7, 0,0,0,0]);
+		let k2tree = K2treeBuilder::new().counts(true).build(matrix);
+		assert!(k2tree.has_counts());
+		assert_eq!(k2tree.count_in_range(0..=3,0..=3),5);
+		assert_eq!(k2tree.out_degree(2),1);
+	}
+}
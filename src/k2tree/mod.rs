@@ -1,13 +1,27 @@
 
 
 use std::{collections::VecDeque, fmt::Debug};
-use core::fmt::Display;
 
 use crate::matrix::Matrix;
 use crate::sequence::Sequence;
 
 use serde::{Serialize, Deserialize};
 
+pub mod builder;
+mod query;
+mod sparse;
+mod batch;
+mod spmv;
+mod counts;
+mod sample;
+mod treap;
+#[cfg(feature = "parallel")]
+mod parallel;
+
+pub use builder::{K2treeBuilder,Background,BuildStats};
+pub use spmv::Weight;
+pub use treap::K2treap;
+
 #[derive(Serialize,Deserialize,Debug)]
 pub struct K2tree<T> where T:Clone{
 	rows:usize,
@@ -15,15 +29,26 @@ pub struct K2tree<T> where T:Clone{
 	virtual_rows:usize,
 	virtual_cols:usize,
 	k:usize,
+	//Value of the cells that queries treat as empty. Trees written before it was stored had
+	//T::default() as background.
+	#[serde(default)]
+	background:T,
+	//Only the upper triangle (i<=j) is stored, queries mirror it
+	#[serde(default)]
+	symmetric:bool,
 	nodes:Sequence<Option<T>>,
-	leaf:Vec<T>
+	leaf:Vec<T>,
+	//Non-background cells under each internal node, by rank (see store_counts)
+	#[serde(default)]
+	counts:Option<Vec<usize>>
 }
 
-fn next_pow(base:usize,n:usize)->usize{
+pub(crate) fn next_pow(base:usize,n:usize)->usize{
 	base.pow(f64::from(n as u32).log2().ceil() as u32)
 }
-impl <T> K2tree<T> where T:Display + Eq + Clone + Default{
+impl <T> K2tree<T> where T:Eq + Clone{
 	
+	//The background of the matrix becomes the background of the tree
 	pub fn new(matrix:Matrix<T>, k:usize) -> K2tree<T> {
 		let rows = matrix.get_rows();
 		let columns = matrix.get_cols();
@@ -34,8 +59,11 @@ impl <T> K2tree<T> where T:Display + Eq + Clone + Default{
 			virtual_rows:size,
 			virtual_cols:size,
 			k,
+			background:matrix.get_background().clone(),
+			symmetric:false,
 			nodes:Sequence::new(None),
 			leaf:Vec::new(),
+			counts:None
 		};
 		tree.build(matrix.expand(size,size));
 		return tree;
@@ -43,7 +71,7 @@ impl <T> K2tree<T> where T:Display + Eq + Clone + Default{
 	pub fn build(&mut self,matrix:Matrix<T>){
 		
 		let mut target = VecDeque::new();
-		target.push_back(matrix.submatrix(0..=matrix.get_cols()-1, 0..=matrix.get_rows()-1));
+		target.push_back(matrix.submatrix(0..=matrix.get_rows()-1, 0..=matrix.get_cols()-1));
 		while target.len() > 0{
 			let current = target.pop_front().unwrap();
 			let mut ranges = Vec::new();
@@ -55,8 +83,8 @@ impl <T> K2tree<T> where T:Display + Eq + Clone + Default{
 					ranges.push((i*elem_r..=(i+1)*elem_r-1,j*elem_c..=(j+1)*elem_c-1));
 				}
 			}
-			for (x,y) in ranges{
-				let submatrix = current.submatrix(y, x);
+			for (rows,cols) in ranges{
+				let submatrix = current.submatrix(rows, cols);
 				if submatrix.elems() == 1{
 					self.leaf.push(submatrix.get(0,0).unwrap().clone());
 					continue;
@@ -75,7 +103,8 @@ impl <T> K2tree<T> where T:Display + Eq + Clone + Default{
 
 		assert!(i<self.get_rows() && j<self.get_cols(),
 		"position overflows k2tree");
-		
+		let (i,j) = if self.symmetric && i > j {(j,i)} else {(i,j)};
+
 		let mut l =1;
 		let mut previous = 0;
 		let mut virtual_x = j;
@@ -115,6 +144,12 @@ impl <T> K2tree<T> where T:Display + Eq + Clone + Default{
 	pub fn get_leaf(&self)->&Vec<T>{
 		&self.leaf
 	}
+	pub fn is_symmetric(&self)->bool{
+		self.symmetric
+	}
+	pub fn get_background(&self)->&T{
+		&self.background
+	}
 	pub fn get_k(&self)->usize{
 		self.k
 	}
@@ -164,6 +199,32 @@ mod tests {
 		println!("compression rate: {}%", (1.0 - (total_size as f64/raw_size as f64)) * 100.0);
 	}
 	
+	#[test]
+	fn test_categorical_background(){
+		let values = vec!["?","a","?","?", "?","?","b","?", "?","?","?","?"];
+		let matrix = Matrix::from_iter_with_background(3,4,String::from("?"),
+			values.into_iter().map(String::from));
+		let k2tree = K2tree::new(matrix,2);
+		assert_eq!(k2tree.get_background(),"?");
+		assert_eq!(k2tree.get(0,1).unwrap(),"a");
+		assert_eq!(k2tree.get(2,3).unwrap(),"?");
+		assert_eq!(k2tree.range(0..=2,0..=3).len(),2);
+	}
+
+	#[test]
+	fn test_serde_without_background(){
+		let mut matrix:Matrix<u8> = Matrix::new(4,4);
+		matrix.set(1,2,3);
+		let k2tree = K2tree::new(matrix,2);
+		let mut value = serde_json::to_value(&k2tree).unwrap();
+		//Layout written before the background field existed
+		value.as_object_mut().unwrap().remove("background");
+		let k2tree:K2tree<u8> = serde_json::from_value(value).unwrap();
+		assert_eq!(k2tree.get_background(),&0);
+		assert_eq!(k2tree.get(1,2),Some(&3));
+		assert_eq!(k2tree.range(0..=3,0..=3).len(),1);
+	}
+
 	#[test]
 	fn test_serde(){
 		
@@ -0,0 +1,158 @@
+use rayon::prelude::*;
+
+use super::{K2tree,next_pow};
+use crate::matrix::Matrix;
+use crate::matrix::submatrix::Submatrix;
+use crate::sequence::Sequence;
+
+//Entries of one first-level subtree, level by level. The last level holds the leaves.
+struct Levels<T>{
+	nodes:Vec<Vec<Option<T>>>,
+	leaf:Vec<T>
+}
+
+impl <T> K2tree<T> where T:Eq + Clone + Send + Sync{
+
+	//Same tree as new, with the k^2 first-level subtrees built on the rayon pool
+	pub fn new_parallel(matrix:Matrix<T>,k:usize)->K2tree<T>{
+		let rows = matrix.get_rows();
+		let columns = matrix.get_cols();
+		let size = std::cmp::max(next_pow(k,rows),next_pow(k,columns));
+		let mut tree = K2tree {
+			rows,
+			columns,
+			virtual_rows:size,
+			virtual_cols:size,
+			k,
+			background:matrix.get_background().clone(),
+			symmetric:false,
+			nodes:Sequence::new(None),
+			leaf:Vec::new(),
+			counts:None
+		};
+		tree.build_parallel(matrix.expand(size,size));
+		tree
+	}
+
+	pub fn build_parallel(&mut self,matrix:Matrix<T>){
+		let k = self.k;
+		let root = matrix.submatrix(0..=matrix.get_rows()-1, 0..=matrix.get_cols()-1);
+		let children = quadrants(&root,k);
+		if root.get_rows()/k == 1{
+			for child in children{
+				self.leaf.push(child.get(0,0).unwrap().clone());
+			}
+			return;
+		}
+		//None for the children stored as a single uniform node
+		let subtrees:Vec<Option<Levels<T>>> = children.par_iter()
+			.map(|child| if child.all_eq() {None} else {Some(levels(child,k))})
+			.collect();
+		for (child,subtree) in children.iter().zip(subtrees.iter()){
+			match subtree{
+				Some(_) => self.nodes.push(None),
+				None => self.nodes.push(Some(child.get(0,0).unwrap().clone()))
+			}
+		}
+		//Level order of the whole tree is every subtree's level, one after another
+		let depth = subtrees.iter().flatten().map(|levels| levels.nodes.len()).max().unwrap_or(0);
+		for level in 0..depth{
+			for levels in subtrees.iter().flatten(){
+				for node in &levels.nodes[level]{
+					self.nodes.push(node.clone());
+				}
+			}
+		}
+		for levels in subtrees.into_iter().flatten(){
+			self.leaf.extend(levels.leaf);
+		}
+	}
+}
+
+fn quadrants<'a,T>(current:&Submatrix<'a,T>,k:usize)->Vec<Submatrix<'a,T>>{
+	let elem_c = current.get_cols()/k;
+	let elem_r = current.get_rows()/k;
+	let mut children = Vec::with_capacity(k*k);
+	for i in 0..k{
+		for j in 0..k{
+			children.push(current.submatrix(i*elem_r..=(i+1)*elem_r-1,j*elem_c..=(j+1)*elem_c-1));
+		}
+	}
+	children
+}
+
+//Depth first walk of a mixed subtree. Children are visited in order, so appending to the
+//vector of each depth leaves every level in level order.
+fn levels<T>(submatrix:&Submatrix<'_,T>,k:usize)->Levels<T> where T:Eq + Clone{
+	let mut levels = Levels{
+		nodes:Vec::new(),
+		leaf:Vec::new()
+	};
+	descend(submatrix,k,0,&mut levels);
+	levels
+}
+
+fn descend<T>(current:&Submatrix<'_,T>,k:usize,depth:usize,levels:&mut Levels<T>) where T:Eq + Clone{
+	let children = quadrants(current,k);
+	if current.get_rows()/k == 1{
+		for child in children{
+			levels.leaf.push(child.get(0,0).unwrap().clone());
+		}
+		return;
+	}
+	if levels.nodes.len() == depth{
+		levels.nodes.push(Vec::new());
+	}
+	let uniform:Vec<bool> = children.iter().map(|child| child.all_eq()).collect();
+	for (child,&uniform) in children.iter().zip(&uniform){
+		if uniform{
+			levels.nodes[depth].push(Some(child.get(0,0).unwrap().clone()));
+		}else{
+			levels.nodes[depth].push(None);
+		}
+	}
+	for (child,&uniform) in children.iter().zip(&uniform){
+		if !uniform{
+			descend(child,k,depth+1,levels);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use rand::Rng;
+	use crate::matrix::Matrix;
+	use crate::k2tree::K2tree;
+
+	fn assert_identical(matrix:Matrix<u8>,k:usize){
+		let sequential = K2tree::new(matrix.clone(),k);
+		let parallel = K2tree::new_parallel(matrix,k);
+		assert_eq!(sequential.get_nodes().get_data(),parallel.get_nodes().get_data());
+		assert_eq!(sequential.get_nodes().get_target_index(),parallel.get_nodes().get_target_index());
+		assert_eq!(sequential.get_leaf(),parallel.get_leaf());
+	}
+
+	#[test]
+	fn parallel_build_is_identical(){
+		let mut rng = rand::thread_rng();
+		for (size,k) in [(2,2),(7,2),(64,2),(100,2),(9,3)]{
+			let mut matrix = Matrix::new(size,size);
+			for _ in 0..size{
+				matrix.set(rng.gen_range(0..size),rng.gen_range(0..size),rng.gen_range(1..4));
+			}
+			assert_identical(matrix,k);
+		}
+	}
+
+	#[test]
+	fn parallel_build_uniform_children(){
+		let mut matrix = Matrix::new(16,16);
+		for i in 0..8{
+			for j in 0..8{
+				matrix.set(i,j,1);
+			}
+		}
+		matrix.set(12,3,2);
+		assert_identical(matrix,2);
+	}
+}
@@ -0,0 +1,297 @@
+use std::ops::{Range,RangeInclusive};
+
+use super::K2tree;
+
+impl <T> K2tree<T> where T:Eq + Clone{
+
+	//Position of the first child of the node at pos (None is the root)
+	pub(crate) fn first_child(&self,pos:Option<usize>)->usize{
+		match pos{
+			None => 0,
+			Some(p) => self.nodes.rank(p).unwrap() * self.k.pow(2)
+		}
+	}
+
+	//Visits every cell of the query window whose value differs from background.
+	//Cells of a node are visited in (y,x) order, so a single row comes out sorted by column
+	//and a single column comes out sorted by row.
+	pub(crate) fn walk<'a,F>(&'a self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>,background:&T,f:&mut F)
+	where F:FnMut(usize,usize,&'a T){
+		if self.rows == 0 || self.columns == 0{
+			return;
+		}
+		//Padding is never reported
+		let rows = *rows.start()..=std::cmp::min(*rows.end(),self.rows-1);
+		let cols = *cols.start()..=std::cmp::min(*cols.end(),self.columns-1);
+		if rows.is_empty() || cols.is_empty(){
+			return;
+		}
+		if !self.symmetric{
+			self.walk_node(None,self.virtual_rows,(0,0),&rows,&cols,background,f);
+			return;
+		}
+		//The lower triangle is stored transposed. For a single row its cells come before the
+		//stored ones (j<i), otherwise after them (i>j), which keeps both orders above.
+		let size = self.virtual_rows;
+		if rows.start() == rows.end(){
+			self.walk_node(None,size,(0,0),&cols,&rows,background,&mut |i,j,value| if i<j {f(j,i,value)});
+			self.walk_node(None,size,(0,0),&rows,&cols,background,f);
+		}else{
+			self.walk_node(None,size,(0,0),&rows,&cols,background,f);
+			self.walk_node(None,size,(0,0),&cols,&rows,background,&mut |i,j,value| if i<j {f(j,i,value)});
+		}
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn walk_node<'a,F>(&'a self,pos:Option<usize>,size:usize,offset:(usize,usize),
+		rows:&RangeInclusive<usize>,cols:&RangeInclusive<usize>,background:&T,f:&mut F)
+	where F:FnMut(usize,usize,&'a T){
+		let start = self.first_child(pos);
+		let sub = size/self.k;
+		for y in 0..self.k{
+			let row_start = offset.0 + y*sub;
+			let row_end = row_start + sub - 1;
+			if row_end < *rows.start() || row_start > *rows.end(){
+				continue;
+			}
+			for x in 0..self.k{
+				let col_start = offset.1 + x*sub;
+				let col_end = col_start + sub - 1;
+				if col_end < *cols.start() || col_start > *cols.end(){
+					continue;
+				}
+				let child = start + y*self.k + x;
+				if child >= self.nodes.len(){
+					let value = &self.leaf[child-self.nodes.len()];
+					if value != background{
+						f(row_start,col_start,value);
+					}
+					continue;
+				}
+				match self.nodes.get(child).unwrap(){
+					None => self.walk_node(Some(child),sub,(row_start,col_start),rows,cols,background,f),
+					Some(value) => {
+						if value == background{
+							continue;
+						}
+						let row_range = std::cmp::max(row_start,*rows.start())..=std::cmp::min(row_end,*rows.end());
+						for i in row_range{
+							let col_range = std::cmp::max(col_start,*cols.start())..=std::cmp::min(col_end,*cols.end());
+							for j in col_range{
+								f(i,j,value);
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+
+	//Whether the window has any non-background cell, stopping at the first one found
+	pub fn any_in_range(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->bool{
+		if self.rows == 0 || self.columns == 0{
+			return false;
+		}
+		let rows = *rows.start()..=std::cmp::min(*rows.end(),self.rows-1);
+		let cols = *cols.start()..=std::cmp::min(*cols.end(),self.columns-1);
+		if rows.is_empty() || cols.is_empty(){
+			return false;
+		}
+		let size = self.virtual_rows;
+		self.any_node(None,size,(0,0),&rows,&cols) || (self.symmetric && self.any_node(None,size,(0,0),&cols,&rows))
+	}
+
+	fn any_node(&self,pos:Option<usize>,size:usize,offset:(usize,usize),
+		rows:&RangeInclusive<usize>,cols:&RangeInclusive<usize>)->bool{
+		let start = self.first_child(pos);
+		let sub = size/self.k;
+		for y in 0..self.k{
+			let row = offset.0 + y*sub;
+			if row+sub-1 < *rows.start() || row > *rows.end(){
+				continue;
+			}
+			for x in 0..self.k{
+				let col = offset.1 + x*sub;
+				if col+sub-1 < *cols.start() || col > *cols.end(){
+					continue;
+				}
+				let child = start + y*self.k + x;
+				let found = match self.child(child){
+					None => self.any_node(Some(child),sub,(row,col),rows,cols),
+					Some(value) => *value != self.background
+				};
+				if found{
+					return true;
+				}
+			}
+		}
+		false
+	}
+
+	//Visits every stored block (uniform node or leaf) whose value differs from background,
+	//clipped to the real rows and columns. Symmetric trees only report the upper triangle.
+	pub(crate) fn walk_blocks<'a,F>(&'a self,f:&mut F) where F:FnMut(Range<usize>,Range<usize>,&'a T){
+		if self.rows == 0 || self.columns == 0{
+			return;
+		}
+		self.walk_blocks_node(None,self.virtual_rows,(0,0),f);
+	}
+
+	fn walk_blocks_node<'a,F>(&'a self,pos:Option<usize>,size:usize,offset:(usize,usize),f:&mut F)
+	where F:FnMut(Range<usize>,Range<usize>,&'a T){
+		let start = self.first_child(pos);
+		let sub = size/self.k;
+		for y in 0..self.k{
+			let row_start = offset.0 + y*sub;
+			if row_start >= self.rows{
+				break;
+			}
+			for x in 0..self.k{
+				let col_start = offset.1 + x*sub;
+				if col_start >= self.columns{
+					break;
+				}
+				let child = start + y*self.k + x;
+				let value = if child >= self.nodes.len(){
+					&self.leaf[child-self.nodes.len()]
+				}else{
+					match self.nodes.get(child).unwrap(){
+						None => {
+							self.walk_blocks_node(Some(child),sub,(row_start,col_start),f);
+							continue;
+						},
+						Some(value) => value
+					}
+				};
+				if *value != self.background{
+					f(row_start..std::cmp::min(row_start+sub,self.rows),col_start..std::cmp::min(col_start+sub,self.columns),value);
+				}
+			}
+		}
+	}
+
+	//Cells of the window whose value differs from background, in tree order
+	pub fn range_with(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>,background:&T)->Vec<((usize,usize),&T)>{
+		let mut output = Vec::new();
+		self.walk(rows,cols,background,&mut |i,j,value| output.push(((i,j),value)));
+		output
+	}
+
+	//Cells of row i whose value differs from background, sorted by column
+	pub fn row_with(&self,i:usize,background:&T)->Vec<(usize,&T)>{
+		assert!(i<self.rows,"row overflows k2tree");
+		let mut output = Vec::new();
+		self.walk(i..=i,0..=self.columns-1,background,&mut |_,j,value| output.push((j,value)));
+		output
+	}
+
+	//Cells of column j whose value differs from background, sorted by row
+	pub fn column_with(&self,j:usize,background:&T)->Vec<(usize,&T)>{
+		assert!(j<self.columns,"column overflows k2tree");
+		let mut output = Vec::new();
+		self.walk(0..=self.rows-1,j..=j,background,&mut |i,_,value| output.push((i,value)));
+		output
+	}
+
+	//Non-background cells of the window
+	pub fn range(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->Vec<((usize,usize),&T)>{
+		self.range_with(rows,cols,&self.background)
+	}
+	//Non-background cells of row i
+	pub fn row(&self,i:usize)->Vec<(usize,&T)>{
+		self.row_with(i,&self.background)
+	}
+	//Non-background cells of column j
+	pub fn column(&self,j:usize)->Vec<(usize,&T)>{
+		self.column_with(j,&self.background)
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::matrix::Matrix;
+	use crate::k2tree::K2tree;
+
+	fn sample()->Matrix<usize>{
+		let mut matrix = Matrix::new(9,9);
+		matrix.set(0,1,1);
+		matrix.set(2,2,3);
+		matrix.set(3,0,1);
+		matrix.set(3,7,2);
+		matrix.set(8,8,5);
+		matrix
+	}
+
+	#[test]
+	fn row_query(){
+		let matrix = sample();
+		let k2tree = K2tree::new(matrix.clone(),2);
+		for i in 0..9{
+			let expected:Vec<(usize,&usize)> = (0..9)
+				.map(|j| (j,matrix.get(i,j).unwrap()))
+				.filter(|(_,v)| **v != 0)
+				.collect();
+			assert_eq!(k2tree.row(i),expected);
+		}
+	}
+
+	#[test]
+	fn column_query(){
+		let matrix = sample();
+		let k2tree = K2tree::new(matrix.clone(),2);
+		for j in 0..9{
+			let expected:Vec<(usize,&usize)> = (0..9)
+				.map(|i| (i,matrix.get(i,j).unwrap()))
+				.filter(|(_,v)| **v != 0)
+				.collect();
+			assert_eq!(k2tree.column(j),expected);
+		}
+	}
+
+	#[test]
+	fn range_query_uniform_block(){
+		let matrix = Matrix::from_iter(4,4,vec![1,1,0,0, 1,1,0,0, 0,0,0,7, 0,0,0,0]);
+		let k2tree = K2tree::new(matrix,2);
+		let mut cells:Vec<((usize,usize),usize)> = k2tree.range(0..=3,1..=3).into_iter().map(|(p,v)| (p,*v)).collect();
+		cells.sort();
+		assert_eq!(cells,vec![((0,1),1),((1,1),1),((2,3),7)]);
+		assert_eq!(k2tree.range_with(0..=1,0..=1,&1).len(),0);
+		assert!(k2tree.any_in_range(1..=2,1..=2));
+		assert!(k2tree.any_in_range(2..=9,3..=9));
+		assert!(!k2tree.any_in_range(2..=3,0..=2));
+	}
+
+	#[test]
+	fn symmetric_queries(){
+		let edges = vec![(0,5,1),(5,2,2),(3,3,3),(4,1,4)];
+		let k2tree = K2tree::from_triplets_symmetric(6,2,0,edges.clone());
+		let mut full = Matrix::new(6,6);
+		for (i,j,value) in edges{
+			full.set(i,j,value);
+			full.set(j,i,value);
+		}
+		for i in 0..6{
+			let expected:Vec<(usize,&i32)> = (0..6).map(|j| (j,full.get(i,j).unwrap())).filter(|(_,v)| **v != 0).collect();
+			assert_eq!(k2tree.row(i),expected);
+			assert_eq!(k2tree.column(i),expected);
+		}
+		let mut cells:Vec<((usize,usize),i32)> = k2tree.range(1..=5,0..=2).into_iter().map(|(p,v)| (p,*v)).collect();
+		cells.sort();
+		assert_eq!(cells,vec![((4,1),4),((5,0),1),((5,2),2)]);
+		assert!(k2tree.any_in_range(5..=5,0..=0));
+		assert!(!k2tree.any_in_range(0..=2,0..=2));
+	}
+
+	#[test]
+	fn queries_skip_background(){
+		let mut matrix = Matrix::with_background(5,5,-9999);
+		matrix.set(0,0,0);
+		matrix.set(4,1,7);
+		matrix.set(4,4,-1);
+		let k2tree = K2tree::new(matrix,2);
+		assert_eq!(*k2tree.get_background(),-9999);
+		assert_eq!(k2tree.row(4),vec![(1,&7),(4,&-1)]);
+		assert_eq!(k2tree.column(0),vec![(0,&0)]);
+		assert_eq!(k2tree.range(0..=4,0..=4).len(),3);
+	}
+}
@@ -0,0 +1,225 @@
+use std::ops::RangeInclusive;
+
+use rand::Rng;
+
+use super::K2tree;
+
+impl <T> K2tree<T> where T:Eq + Clone{
+
+	//The n-th stored cell of the window in tree order. Children fully inside the window take
+	//their size from the stored counts, the ones crossing its border are counted on the way.
+	#[allow(clippy::too_many_arguments)]
+	fn nth_stored(&self,pos:Option<usize>,size:usize,offset:(usize,usize),
+		rows:&RangeInclusive<usize>,cols:&RangeInclusive<usize>,mut n:usize,counts:&[usize])->((usize,usize),&T){
+		let start = self.first_child(pos);
+		let sub = size/self.k;
+		for y in 0..self.k{
+			let row = offset.0 + y*sub;
+			if row+sub-1 < *rows.start() || row > *rows.end(){
+				continue;
+			}
+			for x in 0..self.k{
+				let col = offset.1 + x*sub;
+				if col+sub-1 < *cols.start() || col > *cols.end(){
+					continue;
+				}
+				let child = start + y*self.k + x;
+				match self.child(child){
+					None => {
+						let inside = row >= *rows.start() && row+sub-1 <= *rows.end() && col >= *cols.start() && col+sub-1 <= *cols.end();
+						let count = if inside {
+							counts[self.nodes.rank(child).unwrap()-1]
+						}else{
+							self.count_stored(Some(child),sub,(row,col),rows,cols,counts)
+						};
+						if n < count{
+							return self.nth_stored(Some(child),sub,(row,col),rows,cols,n,counts);
+						}
+						n-=count;
+					},
+					Some(value) => {
+						let block_rows = std::cmp::max(row,*rows.start())..=std::cmp::min(row+sub-1,*rows.end());
+						let block_cols = std::cmp::max(col,*cols.start())..=std::cmp::min(col+sub-1,*cols.end());
+						let count = self.uniform_count(value,block_rows.clone(),block_cols.clone());
+						if n < count{
+							let width = std::cmp::min(*block_cols.end(),self.columns-1) - block_cols.start() + 1;
+							return ((block_rows.start() + n/width,block_cols.start() + n%width),value);
+						}
+						n-=count;
+					}
+				}
+			}
+		}
+		unreachable!("cell index overflows the window");
+	}
+
+	//Non-background cell of the window drawn uniformly, None if there is none. Without stored
+	//counts (see store_counts) the window is walked twice, to count its cells and to pick one.
+	pub fn sample_in_range(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>,rng:&mut impl Rng)->Option<((usize,usize),&T)>{
+		let counts = match &self.counts{
+			None => {
+				let total = self.count_in_range(rows.clone(),cols.clone());
+				if total == 0{
+					return None;
+				}
+				let mut n = rng.gen_range(0..total);
+				let mut found = None;
+				self.walk(rows,cols,&self.background,&mut |i,j,value| {
+					if n == 0 && found.is_none(){
+						found = Some(((i,j),value));
+					}
+					n = n.saturating_sub(1);
+				});
+				return found;
+			},
+			Some(counts) => counts
+		};
+		if self.rows == 0 || self.columns == 0 || rows.is_empty() || cols.is_empty(){
+			return None;
+		}
+		let size = self.virtual_rows;
+		let direct = self.count_stored(None,size,(0,0),&rows,&cols,counts);
+		if !self.symmetric{
+			if direct == 0{
+				return None;
+			}
+			return Some(self.nth_stored(None,size,(0,0),&rows,&cols,rng.gen_range(0..direct),counts));
+		}
+		//Cells below the diagonal come mirrored from the transposed window. Its diagonal cells
+		//are already in the direct one, so drawing one of them means drawing again.
+		let transposed = self.count_stored(None,size,(0,0),&cols,&rows,counts);
+		if direct + transposed == 0{
+			return None;
+		}
+		loop{
+			let n = rng.gen_range(0..direct+transposed);
+			if n < direct{
+				return Some(self.nth_stored(None,size,(0,0),&rows,&cols,n,counts));
+			}
+			let ((i,j),value) = self.nth_stored(None,size,(0,0),&cols,&rows,n-direct,counts);
+			if i != j{
+				return Some(((j,i),value));
+			}
+		}
+	}
+
+	//Non-background cell drawn uniformly from the whole matrix
+	pub fn sample_cell(&self,rng:&mut impl Rng)->Option<((usize,usize),&T)>{
+		if self.rows == 0 || self.columns == 0{
+			return None;
+		}
+		self.sample_in_range(0..=self.rows-1,0..=self.columns-1,rng)
+	}
+
+	//Nodes visited stepping len times to a uniformly chosen successor (row neighbour),
+	//start included. Stops early at a node without successors.
+	pub fn random_walk(&self,start:usize,len:usize,rng:&mut impl Rng)->Vec<usize>{
+		assert!(self.rows == self.columns,"graph must be square");
+		assert!(start<self.rows,"node overflows graph");
+		let mut walk = Vec::with_capacity(len+1);
+		walk.push(start);
+		let mut node = start;
+		for _ in 0..len{
+			match self.sample_in_range(node..=node,0..=self.columns-1,rng){
+				Some(((_,next),_)) => {
+					node = next;
+					walk.push(node);
+				},
+				None => break
+			}
+		}
+		walk
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use std::collections::HashMap;
+	use rand::SeedableRng;
+	use rand::rngs::StdRng;
+	use crate::matrix::Matrix;
+	use crate::k2tree::K2tree;
+
+	#[test]
+	fn samples_are_uniform(){
+		let mut matrix = Matrix::new(10,10);
+		for i in 0..4{
+			for j in 0..4{
+				matrix.set(i,j,1);
+			}
+		}
+		matrix.set(9,9,2);
+		matrix.set(6,1,3);
+		matrix.set(7,8,4);
+		let mut k2tree = K2tree::new(matrix.clone(),2);
+		k2tree.store_counts();
+		let mut rng = StdRng::seed_from_u64(7);
+		let mut seen:HashMap<(usize,usize),usize> = HashMap::new();
+		let draws = 19000;
+		for _ in 0..draws{
+			let ((i,j),value) = k2tree.sample_cell(&mut rng).unwrap();
+			assert_eq!(value,matrix.get(i,j).unwrap());
+			*seen.entry((i,j)).or_insert(0)+=1;
+		}
+		assert_eq!(seen.len(),19);
+		//1000 expected per cell
+		assert!(seen.values().all(|&count| count > 800 && count < 1200));
+		let ((i,_),_) = k2tree.sample_in_range(5..=9,0..=9,&mut rng).unwrap();
+		assert!(i >= 5);
+		assert!(k2tree.sample_in_range(4..=5,4..=9,&mut rng).is_none());
+	}
+
+	#[test]
+	fn samples_without_counts(){
+		let arcs = [(0,1),(0,3),(2,2),(3,0)];
+		let k2tree = K2tree::from_triplets(4,4,2,false,arcs.iter().map(|&(i,j)| (i,j,true)));
+		assert!(!k2tree.has_counts());
+		let mut rng = StdRng::seed_from_u64(5);
+		let mut seen:HashMap<(usize,usize),usize> = HashMap::new();
+		for _ in 0..4000{
+			let (cell,_) = k2tree.sample_cell(&mut rng).unwrap();
+			*seen.entry(cell).or_insert(0)+=1;
+		}
+		assert_eq!(seen.len(),4);
+		assert!(seen.values().all(|&count| count > 850 && count < 1150));
+		assert_eq!(k2tree.sample_in_range(2..=3,0..=1,&mut rng),Some(((3,0),&true)));
+		assert!(k2tree.sample_in_range(1..=1,0..=3,&mut rng).is_none());
+		assert_eq!(k2tree.random_walk(3,2,&mut rng)[1],0);
+		let symmetric = K2tree::from_triplets_symmetric(6,2,false,vec![(0,4,true)]);
+		assert_eq!(symmetric.random_walk(4,1,&mut rng),vec![4,0]);
+	}
+
+	#[test]
+	fn symmetric_samples(){
+		let mut k2tree = K2tree::from_triplets_symmetric(6,2,false,vec![(0,4,true),(2,2,true)]);
+		k2tree.store_counts();
+		let mut rng = StdRng::seed_from_u64(3);
+		let mut seen:HashMap<(usize,usize),usize> = HashMap::new();
+		for _ in 0..3000{
+			let (cell,_) = k2tree.sample_cell(&mut rng).unwrap();
+			*seen.entry(cell).or_insert(0)+=1;
+		}
+		let mut cells:Vec<(usize,usize)> = seen.keys().copied().collect();
+		cells.sort();
+		assert_eq!(cells,vec![(0,4),(2,2),(4,0)]);
+		assert!(seen.values().all(|&count| count > 850 && count < 1150));
+		assert_eq!(k2tree.random_walk(4,1,&mut rng),vec![4,0]);
+	}
+
+	#[test]
+	fn walks_follow_arcs(){
+		let arcs = [(0,1),(1,2),(1,3),(2,0),(3,0),(3,4)];
+		let mut k2tree = K2tree::from_triplets(5,5,2,false,arcs.iter().map(|&(i,j)| (i,j,true)));
+		k2tree.store_counts();
+		let mut rng = StdRng::seed_from_u64(11);
+		for _ in 0..50{
+			let walk = k2tree.random_walk(0,8,&mut rng);
+			assert_eq!(walk[0],0);
+			for step in walk.windows(2){
+				assert!(arcs.contains(&(step[0],step[1])));
+			}
+			//4 has no successors
+			assert!(walk.len() == 9 || *walk.last().unwrap() == 4);
+		}
+	}
+}
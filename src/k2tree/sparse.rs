@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+
+use super::{K2tree,next_pow};
+use crate::sequence::Sequence;
+use crate::sparse::{Coo,Csr};
+
+impl <T> K2tree<T> where T:Eq + Clone{
+
+	//Builds the tree straight from the listed cells, without going through a dense matrix.
+	//Cells not listed (and the padding) take the background value. Duplicated positions keep
+	//the last value.
+	pub fn from_triplets(rows:usize,columns:usize,k:usize,background:T,
+		triplets:impl IntoIterator<Item=(usize,usize,T)>)->K2tree<T>{
+		assert!(rows>0 && columns>0);
+		assert!(k>1,"k must be greater than 1");
+		let size = std::cmp::max(k,std::cmp::max(next_pow(k,rows),next_pow(k,columns)));
+		let mut points:Vec<(usize,usize,T)> = Vec::new();
+		for (i,j,value) in triplets{
+			assert!(i<rows && j<columns,"position overflows matrix");
+			points.push((i,j,value));
+		}
+		//Stable sort, so the last duplicate stays last
+		points.sort_by_key(|&(i,j,_)| (i,j));
+		let mut deduplicated:Vec<(usize,usize,T)> = Vec::with_capacity(points.len());
+		for point in points{
+			match deduplicated.last_mut(){
+				Some(last) if last.0 == point.0 && last.1 == point.1 => *last = point,
+				_ => deduplicated.push(point)
+			}
+		}
+		deduplicated.retain(|(_,_,value)| *value != background);
+
+		let mut tree = K2tree{
+			rows,
+			columns,
+			virtual_rows:size,
+			virtual_cols:size,
+			k,
+			background,
+			symmetric:false,
+			nodes:Sequence::new(None),
+			leaf:Vec::new(),
+			counts:None
+		};
+		tree.build_sparse(deduplicated);
+		tree
+	}
+
+	//Symmetric tree of side n. Every cell (i,j) also sets (j,i), only the upper triangle is stored.
+	pub fn from_triplets_symmetric(n:usize,k:usize,background:T,
+		triplets:impl IntoIterator<Item=(usize,usize,T)>)->K2tree<T>{
+		let upper = triplets.into_iter().map(|(i,j,value)| (std::cmp::min(i,j),std::cmp::max(i,j),value));
+		let mut tree = Self::from_triplets(n,n,k,background,upper);
+		tree.symmetric = true;
+		tree
+	}
+
+	pub fn from_coo(coo:&Coo<T>,k:usize)->K2tree<T>{
+		Self::from_triplets(coo.get_rows(),coo.get_cols(),k,coo.get_background().clone(),
+			coo.iter().map(|(i,j,value)| (i,j,value.clone())))
+	}
+
+	pub fn from_csr(csr:&Csr<T>,k:usize)->K2tree<T>{
+		Self::from_triplets(csr.get_rows(),csr.get_cols(),k,csr.get_background().clone(),
+			csr.iter().map(|(i,j,value)| (i,j,value.clone())))
+	}
+
+	fn build_sparse(&mut self,points:Vec<(usize,usize,T)>){
+		//(row offset, column offset, side, cells inside)
+		let mut target = VecDeque::new();
+		target.push_back((0,0,self.virtual_rows,points));
+		while let Some((row_off,col_off,size,points)) = target.pop_front(){
+			let sub = size/self.k;
+			let mut buckets:Vec<Vec<(usize,usize,T)>> = (0..self.k*self.k).map(|_| Vec::new()).collect();
+			for point in points{
+				let y = (point.0-row_off)/sub;
+				let x = (point.1-col_off)/sub;
+				buckets[y*self.k+x].push(point);
+			}
+			for (index,bucket) in buckets.into_iter().enumerate(){
+				let child_row = row_off + (index/self.k)*sub;
+				let child_col = col_off + (index%self.k)*sub;
+				if sub == 1{
+					self.leaf.push(bucket.into_iter().next().map(|(_,_,value)| value).unwrap_or_else(|| self.background.clone()));
+					continue;
+				}
+				if bucket.is_empty(){
+					self.nodes.push(Some(self.background.clone()));
+					continue;
+				}
+				let inside = child_row + sub <= self.rows && child_col + sub <= self.columns;
+				if inside && bucket.len() == sub*sub && bucket.iter().all(|(_,_,value)| *value == bucket[0].2){
+					self.nodes.push(Some(bucket[0].2.clone()));
+				}else{
+					self.nodes.push(None);
+					target.push_back((child_row,child_col,sub,bucket));
+				}
+			}
+		}
+	}
+
+	//Streams the rows of the tree in order, skipping background cells
+	pub fn to_coo(&self)->Coo<T>{
+		let mut coo = Coo::new(self.rows,self.columns,self.background.clone());
+		for i in 0..self.rows{
+			for (j,value) in self.row(i){
+				coo.push(i,j,value.clone());
+			}
+		}
+		coo
+	}
+
+	pub fn to_csr(&self)->Csr<T>{
+		let mut row_ptr = Vec::with_capacity(self.rows+1);
+		let mut col_idx = Vec::new();
+		let mut values = Vec::new();
+		row_ptr.push(0);
+		for i in 0..self.rows{
+			for (j,value) in self.row(i){
+				col_idx.push(j);
+				values.push(value.clone());
+			}
+			row_ptr.push(values.len());
+		}
+		Csr::new(self.rows,self.columns,self.background.clone(),row_ptr,col_idx,values)
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::matrix::Matrix;
+	use crate::k2tree::K2tree;
+	use crate::sparse::{Coo,Csr};
+
+	#[test]
+	fn sparse_build_matches_dense(){
+		let mut matrix = Matrix::new(9,9);
+		matrix.set(0,1,1);
+		matrix.set(2,2,1);
+		matrix.set(3,0,1);
+		for i in 4..8{
+			for j in 4..8{
+				matrix.set(i,j,2);
+			}
+		}
+		let coo = Coo::from_matrix(&matrix);
+		let dense = K2tree::new(matrix.clone(),2);
+		let sparse = K2tree::from_coo(&coo,2);
+		assert_eq!(dense.get_nodes().get_data(),sparse.get_nodes().get_data());
+		assert_eq!(dense.get_leaf(),sparse.get_leaf());
+		for i in 0..9{
+			for j in 0..9{
+				assert_eq!(matrix.get(i,j),sparse.get(i,j));
+			}
+		}
+	}
+
+	#[test]
+	fn csr_roundtrip(){
+		let csr = Csr::new(3,5,0,vec![0,2,2,3],vec![1,4,0],vec![7,8,9]);
+		let k2tree = K2tree::from_csr(&csr,2);
+		assert_eq!(k2tree.to_csr(),csr);
+		assert_eq!(k2tree.to_coo(),csr.to_coo());
+	}
+
+	#[test]
+	fn non_default_background(){
+		let coo = Coo::from_triplets(4,4,-9999,vec![(1,2,5),(3,3,-9999)]);
+		let k2tree = K2tree::from_coo(&coo,2);
+		assert_eq!(*k2tree.get(0,0).unwrap(),-9999);
+		assert_eq!(*k2tree.get(1,2).unwrap(),5);
+		assert_eq!(*k2tree.get_background(),-9999);
+		assert_eq!(k2tree.to_coo().iter().collect::<Vec<_>>(),vec![(1,2,&5)]);
+	}
+}
@@ -0,0 +1,132 @@
+use super::K2tree;
+use crate::real::Real;
+
+//Numeric value of a cell for the products. true is 1, background cells count as 0.
+pub trait Weight{
+	fn weight(&self)->f64;
+}
+
+impl Weight for bool{
+	fn weight(&self)->f64{
+		if *self {1.0} else {0.0}
+	}
+}
+
+impl Weight for Real{
+	fn weight(&self)->f64{
+		self.0
+	}
+}
+
+macro_rules! weight_as{
+	($($t:ty),*) => {$(
+		impl Weight for $t{
+			fn weight(&self)->f64{
+				*self as f64
+			}
+		}
+	)*};
+}
+
+weight_as!(u8,u16,u32,u64,usize,i8,i16,i32,i64,isize);
+
+fn prefix_sums(x:&[f64])->Vec<f64>{
+	let mut prefix = Vec::with_capacity(x.len()+1);
+	prefix.push(0.0);
+	for value in x{
+		prefix.push(prefix.last().unwrap() + value);
+	}
+	prefix
+}
+
+impl <T> K2tree<T> where T:Eq + Clone + Weight{
+
+	//y = A x in a single traversal. A uniform block adds its value times the sum of its slice
+	//of x (taken from prefix sums) to each of its rows, without expanding its cells.
+	pub fn spmv(&self,x:&[f64])->Vec<f64>{
+		assert_eq!(x.len(),self.columns,"vector length must match the columns");
+		self.product(x,false)
+	}
+
+	//y = A^T x, same traversal with rows and columns swapped
+	pub fn spmv_transpose(&self,x:&[f64])->Vec<f64>{
+		assert_eq!(x.len(),self.rows,"vector length must match the rows");
+		self.product(x,true)
+	}
+
+	fn product(&self,x:&[f64],transpose:bool)->Vec<f64>{
+		let prefix = prefix_sums(x);
+		let mut y = vec![0.0;if transpose {self.columns} else {self.rows}];
+		let symmetric = self.symmetric;
+		self.walk_blocks(&mut |rows,cols,value| {
+			let (rows,cols) = if transpose {(cols,rows)} else {(rows,cols)};
+			let weight = value.weight();
+			let sum = prefix[cols.end]-prefix[cols.start];
+			for i in rows.clone(){
+				y[i]+=weight*sum;
+			}
+			//Blocks off the diagonal also stand for their mirrored copy
+			if symmetric && rows.start != cols.start{
+				let sum = prefix[rows.end]-prefix[rows.start];
+				for j in cols{
+					y[j]+=weight*sum;
+				}
+			}
+		});
+		y
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::matrix::Matrix;
+	use crate::k2tree::K2tree;
+
+	fn dense_product(matrix:&Matrix<i32>,x:&[f64],transpose:bool)->Vec<f64>{
+		let (rows,cols) = (matrix.get_orig_rows(),matrix.get_orig_cols());
+		let mut y = vec![0.0;if transpose {cols} else {rows}];
+		for i in 0..rows{
+			for j in 0..cols{
+				let value = *matrix.get(i,j).unwrap() as f64;
+				if transpose{
+					y[j]+=value*x[i];
+				}else{
+					y[i]+=value*x[j];
+				}
+			}
+		}
+		y
+	}
+
+	#[test]
+	fn spmv_matches_dense(){
+		let mut matrix = Matrix::new(6,7);
+		for i in 0..4{
+			for j in 0..4{
+				matrix.set(i,j,2);
+			}
+		}
+		matrix.set(5,6,3);
+		matrix.set(4,1,-1);
+		let k2tree = K2tree::new(matrix.clone(),2);
+		let x:Vec<f64> = (0..7).map(|e| e as f64 + 0.5).collect();
+		assert_eq!(k2tree.spmv(&x),dense_product(&matrix,&x,false));
+		let x:Vec<f64> = (0..6).map(|e| 1.0 - e as f64).collect();
+		assert_eq!(k2tree.spmv_transpose(&x),dense_product(&matrix,&x,true));
+	}
+
+	#[test]
+	fn spmv_symmetric_and_bool(){
+		let k2tree = K2tree::from_triplets_symmetric(5,2,0,vec![(0,3,2),(4,1,5),(2,2,7)]);
+		let mut matrix = Matrix::new(5,5);
+		for (i,j,value) in [(0,3,2),(3,0,2),(4,1,5),(1,4,5),(2,2,7)]{
+			matrix.set(i,j,value);
+		}
+		let x = vec![1.0,2.0,3.0,4.0,5.0];
+		assert_eq!(k2tree.spmv(&x),dense_product(&matrix,&x,false));
+		assert_eq!(k2tree.spmv_transpose(&x),dense_product(&matrix,&x,false));
+
+		let graph = K2tree::from_triplets(3,3,2,false,vec![(0,1,true),(0,2,true),(2,0,true)]);
+		assert_eq!(graph.spmv(&[1.0,1.0,1.0]),vec![2.0,0.0,1.0]);
+	}
+}
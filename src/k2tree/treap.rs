@@ -0,0 +1,272 @@
+use std::cmp::{Ordering,Reverse};
+use std::collections::BinaryHeap;
+use std::ops::RangeInclusive;
+
+use serde::{Serialize, Deserialize};
+
+use super::K2tree;
+
+//K2tree plus the largest non-background value under every internal node (k2-treap). Top-k
+//queries expand the subtrees with the largest bound first and stop once k cells came out,
+//so they only visit the part of the window that holds the answer.
+#[derive(Serialize,Deserialize,Debug)]
+#[serde(bound(deserialize = "T: Deserialize<'de> + Default"))]
+pub struct K2treap<T> where T:Clone{
+	tree:K2tree<T>,
+	//By rank of the internal node, None when it has only padding
+	maxima:Vec<Option<T>>
+}
+
+//Something the query still has to look at, with cells in output order (transposed for the
+//mirrored half of symmetric trees)
+enum Candidate{
+	Node{pos:usize,size:usize,offset:(usize,usize),transposed:bool},
+	//Cells of a uniform block clipped to the window, from the next-th one on
+	Block{rows:RangeInclusive<usize>,cols:RangeInclusive<usize>,next:usize,transposed:bool}
+}
+
+//Heap entry: upper bound of the values inside and the first cell it could give
+struct Entry<'a,T>{
+	value:&'a T,
+	position:(usize,usize),
+	candidate:Candidate
+}
+
+//Largest value first, ties by position
+impl<T> Ord for Entry<'_,T> where T:Ord{
+	fn cmp(&self,other:&Self)->Ordering{
+		self.value.cmp(other.value).then_with(|| Reverse(self.position).cmp(&Reverse(other.position)))
+	}
+}
+impl<T> PartialOrd for Entry<'_,T> where T:Ord{
+	fn partial_cmp(&self,other:&Self)->Option<Ordering>{
+		Some(self.cmp(other))
+	}
+}
+impl<T> PartialEq for Entry<'_,T> where T:Ord{
+	fn eq(&self,other:&Self)->bool{
+		self.cmp(other) == Ordering::Equal
+	}
+}
+impl<T> Eq for Entry<'_,T> where T:Ord{}
+
+fn clip(range:&RangeInclusive<usize>,start:usize,end:usize)->RangeInclusive<usize>{
+	std::cmp::max(start,*range.start())..=std::cmp::min(end,*range.end())
+}
+
+impl<T> K2treap<T> where T:Ord + Clone{
+	pub fn new(tree:K2tree<T>)->Self{
+		let internal = tree.nodes.iter().filter(|node| node.is_none()).count();
+		let mut maxima = vec![None;internal];
+		if tree.rows > 0 && tree.columns > 0{
+			Self::max_subtree(&tree,None,tree.virtual_rows,(0,0),&mut maxima);
+		}
+		K2treap{tree,maxima}
+	}
+
+	fn max_subtree(tree:&K2tree<T>,pos:Option<usize>,size:usize,offset:(usize,usize),maxima:&mut Vec<Option<T>>)->Option<T>{
+		let start = tree.first_child(pos);
+		let sub = size/tree.k;
+		let mut max:Option<T> = None;
+		for y in 0..tree.k{
+			for x in 0..tree.k{
+				let (row,col) = (offset.0 + y*sub,offset.1 + x*sub);
+				let child = start + y*tree.k + x;
+				let value = match tree.child(child){
+					None => Self::max_subtree(tree,Some(child),sub,(row,col),maxima),
+					Some(value) if tree.uniform_count(value,row..=row+sub-1,col..=col+sub-1) > 0 => Some(value.clone()),
+					Some(_) => None
+				};
+				if value > max{
+					max = value;
+				}
+			}
+		}
+		if let Some(pos) = pos{
+			maxima[tree.nodes.rank(pos).unwrap()-1] = max.clone();
+		}
+		max
+	}
+
+	pub fn get_tree(&self)->&K2tree<T>{
+		&self.tree
+	}
+	pub fn into_tree(self)->K2tree<T>{
+		self.tree
+	}
+	pub fn get(&self,i:usize,j:usize)->Option<&T>{
+		self.tree.get(i,j)
+	}
+
+	//Pushes the children of the node that meet the window. The window is transposed together
+	//with the node when it comes from the mirrored half.
+	fn expand<'a>(&'a self,heap:&mut BinaryHeap<Entry<'a,T>>,pos:Option<usize>,size:usize,offset:(usize,usize),
+		window:(&RangeInclusive<usize>,&RangeInclusive<usize>),transposed:bool){
+		let tree = &self.tree;
+		let (rows,cols) = if transposed {(window.1,window.0)} else {window};
+		let start = tree.first_child(pos);
+		let sub = size/tree.k;
+		for y in 0..tree.k{
+			let row = offset.0 + y*sub;
+			if row+sub-1 < *rows.start() || row > *rows.end(){
+				continue;
+			}
+			for x in 0..tree.k{
+				let col = offset.1 + x*sub;
+				if col+sub-1 < *cols.start() || col > *cols.end(){
+					continue;
+				}
+				let child = start + y*tree.k + x;
+				let block_rows = clip(rows,row,row+sub-1);
+				let block_cols = clip(cols,col,col+sub-1);
+				match tree.child(child){
+					None => {
+						if let Some(value) = &self.maxima[tree.nodes.rank(child).unwrap()-1]{
+							let corner = (*block_rows.start(),*block_cols.start());
+							heap.push(Entry{
+								value,
+								position:if transposed {(corner.1,corner.0)} else {corner},
+								candidate:Candidate::Node{pos:child,size:sub,offset:(row,col),transposed}
+							});
+						}
+					},
+					Some(value) => {
+						if *value != tree.background{
+							self.push_block(heap,value,block_rows,block_cols,0,transposed);
+						}
+					}
+				}
+			}
+		}
+	}
+
+	//Pushes the block from its next-th cell in output order. The mirrored half leaves out the
+	//stored diagonal, which the direct half already gives.
+	fn push_block<'a>(&'a self,heap:&mut BinaryHeap<Entry<'a,T>>,value:&'a T,
+		rows:RangeInclusive<usize>,cols:RangeInclusive<usize>,mut next:usize,transposed:bool){
+		let height = rows.end() - rows.start() + 1;
+		let width = cols.end() - cols.start() + 1;
+		while next < height*width{
+			let position = if transposed{
+				(cols.start() + next/height,rows.start() + next%height)
+			}else{
+				(rows.start() + next/width,cols.start() + next%width)
+			};
+			if !transposed || position.1 < position.0{
+				heap.push(Entry{value,position,candidate:Candidate::Block{rows,cols,next,transposed}});
+				return;
+			}
+			next+=1;
+		}
+	}
+
+	//The k largest non-background cells of the window, largest first and ties by position
+	pub fn top_k(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>,k:usize)->Vec<((usize,usize),&T)>{
+		let tree = &self.tree;
+		let mut output = Vec::new();
+		if tree.rows == 0 || tree.columns == 0 || k == 0{
+			return output;
+		}
+		let rows = *rows.start()..=std::cmp::min(*rows.end(),tree.rows-1);
+		let cols = *cols.start()..=std::cmp::min(*cols.end(),tree.columns-1);
+		if rows.is_empty() || cols.is_empty(){
+			return output;
+		}
+		let mut heap = BinaryHeap::new();
+		self.expand(&mut heap,None,tree.virtual_rows,(0,0),(&rows,&cols),false);
+		if tree.symmetric{
+			self.expand(&mut heap,None,tree.virtual_rows,(0,0),(&rows,&cols),true);
+		}
+		while let Some(Entry{value,position,candidate}) = heap.pop(){
+			match candidate{
+				Candidate::Node{pos,size,offset,transposed} => {
+					self.expand(&mut heap,Some(pos),size,offset,(&rows,&cols),transposed);
+				},
+				Candidate::Block{rows:block_rows,cols:block_cols,next,transposed} => {
+					output.push((position,value));
+					if output.len() == k{
+						break;
+					}
+					self.push_block(&mut heap,value,block_rows,block_cols,next+1,transposed);
+				}
+			}
+		}
+		output
+	}
+
+	//Largest non-background cell of the window
+	pub fn max_in_range(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->Option<((usize,usize),&T)>{
+		self.top_k(rows,cols,1).pop()
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use rand::Rng;
+	use crate::matrix::Matrix;
+	use crate::k2tree::K2tree;
+	use super::K2treap;
+
+	fn brute_force(matrix:&Matrix<u8>,rows:std::ops::RangeInclusive<usize>,cols:std::ops::RangeInclusive<usize>)->Vec<((usize,usize),u8)>{
+		let mut cells = Vec::new();
+		for i in rows{
+			for j in cols.clone(){
+				if i < matrix.get_rows() && j < matrix.get_cols() && *matrix.get(i,j).unwrap() != 0{
+					cells.push(((i,j),*matrix.get(i,j).unwrap()));
+				}
+			}
+		}
+		cells.sort_by(|a,b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+		cells
+	}
+
+	#[test]
+	fn top_k_matches_sorting(){
+		let mut rng = rand::thread_rng();
+		for (size,k) in [(2,2),(9,2),(20,2),(9,3)]{
+			let mut matrix = Matrix::new(size,size);
+			for i in 0..size{
+				for j in 0..size{
+					if rng.gen_bool(0.3){
+						matrix.set(i,j,rng.gen_range(1..6u8));
+					}
+				}
+			}
+			//A uniform block so that whole nodes are values too
+			for i in 0..std::cmp::min(size,4){
+				for j in 0..std::cmp::min(size,4){
+					matrix.set(i,j,9);
+				}
+			}
+			let treap = K2treap::new(K2tree::new(matrix.clone(),k));
+			for _ in 0..30{
+				let (a,b) = (rng.gen_range(0..size),rng.gen_range(0..size));
+				let (c,d) = (rng.gen_range(0..size),rng.gen_range(0..size));
+				let (rows,cols) = (a.min(b)..=a.max(b)+2,c.min(d)..=c.max(d));
+				let expected = brute_force(&matrix,rows.clone(),cols.clone());
+				for count in [0,1,5,expected.len()+1]{
+					let found:Vec<((usize,usize),u8)> = treap.top_k(rows.clone(),cols.clone(),count).into_iter().map(|(cell,&v)| (cell,v)).collect();
+					assert_eq!(found,expected.iter().take(count).copied().collect::<Vec<_>>());
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn heaviest_edges(){
+		let edges = vec![(0,1,4),(0,5,7),(2,3,7),(3,3,1),(5,0,2),(6,7,9)];
+		let treap = K2treap::new(K2tree::from_triplets(8,8,2,0,edges));
+		assert_eq!(treap.top_k(0..=7,0..=7,3),vec![((6,7),&9),((0,5),&7),((2,3),&7)]);
+		assert_eq!(treap.top_k(0..=5,0..=3,2),vec![((2,3),&7),((0,1),&4)]);
+		assert_eq!(treap.max_in_range(5..=5,0..=7),Some(((5,0),&2)));
+		assert_eq!(treap.max_in_range(4..=4,0..=7),None);
+	}
+
+	#[test]
+	fn symmetric_top_k(){
+		let edges = vec![(0,4,5),(2,2,8),(1,3,3)];
+		let treap = K2treap::new(K2tree::from_triplets_symmetric(6,2,0,edges));
+		assert_eq!(treap.top_k(0..=5,0..=5,10),vec![((2,2),&8),((0,4),&5),((4,0),&5),((1,3),&3),((3,1),&3)]);
+		assert_eq!(treap.top_k(3..=5,0..=2,10),vec![((4,0),&5),((3,1),&3)]);
+	}
+}
@@ -3,10 +3,21 @@ mod matrix;
 mod k2tree;
 mod sequence;
 mod webgraph;
+mod sparse;
+mod reorder;
+mod interleaved;
+mod real;
+pub mod graph;
+pub mod triples;
 
-pub use crate::k2tree::K2tree;
+pub use crate::k2tree::{K2tree,K2treeBuilder,Background,BuildStats,Weight,K2treap};
 pub use crate::matrix::Matrix;
+pub use crate::matrix::iter::{Row,Column,RowMut,ColumnMut};
 pub use crate::sequence::Sequence;
+pub use crate::sparse::{Coo,Csr};
+pub use crate::reorder::{Reordering,Permutation,ReorderedK2tree};
+pub use crate::interleaved::InterleavedK2tree;
+pub use crate::real::Real;
 pub use crate::webgraph::*;
 #[cfg(test)]
 mod tests {
@@ -3,7 +3,7 @@ use super::Matrix;
 use std::fmt;
 use core::fmt::Display;
 
-impl <T> Display for Matrix<T> where T:Display+Default{
+impl <T> Display for Matrix<T> where T:Display{
 	fn fmt(&self, f: &mut fmt::Formatter<'_>)-> fmt::Result {
 		let mut output = String::new();
 		for i in 0..self.rows{
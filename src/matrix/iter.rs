@@ -5,22 +5,49 @@ pub struct MatrixIterator <'a,T>{
 	current_i:usize,
 	current_j:usize,
 }
-/*
+
 pub struct MatrixIteratorOwned<T>{
-	matrix:Matrix<T>,
+	inner:std::vec::IntoIter<T>,
+	columns:usize,
+	virtual_rows:usize,
+	virtual_cols:usize,
+	background:T,
 	current_i:usize,
 	current_j:usize,
 }
-*/
 
-impl <'a,T> Iterator for MatrixIterator<'a,T> where T:Default{
+//Read only view over one row of the matrix, padding included
+pub struct Row<'a,T>{
+	matrix:&'a Matrix<T>,
+	i:usize
+}
+
+//Read only view over one column of the matrix, padding included
+pub struct Column<'a,T>{
+	matrix:&'a Matrix<T>,
+	j:usize
+}
+
+//Mutable view over one row of the virtual matrix, padding included
+pub struct RowMut<'a,T>{
+	cells:&'a mut [T],
+	i:usize
+}
+
+//Mutable view over one column of the virtual matrix, padding included
+pub struct ColumnMut<'a,T>{
+	cells:Vec<&'a mut T>,
+	j:usize
+}
+
+impl <'a,T> Iterator for MatrixIterator<'a,T>{
 	type Item=&'a T;
 
 	fn next(&mut self) -> Option<&'a T>{
-		
+
 		if self.current_i >= self.matrix.get_rows()
 		 || self.current_j >= self.matrix.get_cols(){
-			
+
 			return None;
 		}
 		match self.matrix.get(self.current_i,self.current_j){
@@ -38,33 +65,33 @@ impl <'a,T> Iterator for MatrixIterator<'a,T> where T:Default{
 	}
 }
 
-/* 
-impl <T> Iterator for MatrixIteratorOwned<T> where T:Default{
+impl <T> Iterator for MatrixIteratorOwned<T> where T:Clone{
 	type Item=T;
 
 	fn next(&mut self) -> Option<T>{
-		
-		if self.current_i >= self.matrix.get_virtual_rows()
-		 || self.current_j >= self.matrix.get_virtual_cols(){
-			
+
+		if self.current_i >= self.virtual_rows
+		 || self.current_j >= self.virtual_cols{
+
 			return None;
 		}
-		match self.matrix.get_or_default(self.current_i,self.current_j){
-			None=>None,
-			Some(n) => {
-				if self.current_j + 1 == self.matrix.get_virtual_cols(){
-					self.current_j=0;
-					self.current_i+=1;
-				}else{
-					self.current_j+=1;
-				}
-				Some(n)
-			}
+		//Stored cells are consumed in row-major order, the padding is filled with background
+		let item = if self.current_j < self.columns{
+			self.inner.next().unwrap_or_else(|| self.background.clone())
+		}else{
+			self.background.clone()
+		};
+		if self.current_j + 1 == self.virtual_cols{
+			self.current_j=0;
+			self.current_i+=1;
+		}else{
+			self.current_j+=1;
 		}
+		Some(item)
 	}
 }
-*/
-impl <'a,T> IntoIterator for &'a Matrix<T> where T:Default{
+
+impl <'a,T> IntoIterator for &'a Matrix<T>{
 	type Item = &'a T;
 	type IntoIter = MatrixIterator<'a,T>;
 	fn into_iter(self) -> Self::IntoIter{
@@ -76,30 +103,220 @@ impl <'a,T> IntoIterator for &'a Matrix<T> where T:Default{
 	}
 }
 
-/* 
-impl <T> IntoIterator for Matrix<T>{
+impl <T> IntoIterator for Matrix<T> where T:Clone{
 	type Item = T;
-	type IntoIter= std::vec::IntoIter<T>;
+	type IntoIter= MatrixIteratorOwned<T>;
 	fn into_iter(self) -> Self::IntoIter{
-		self.inner.into_iter()
+		MatrixIteratorOwned{
+			inner:self.inner.into_iter(),
+			columns:self.columns,
+			virtual_rows:self.virtual_rows,
+			virtual_cols:self.virtual_cols,
+			background:self.background,
+			current_i:0,
+			current_j:0
+		}
 	}
 }
 
-impl <'a,T> IntoIterator for &'a Matrix<T>{
-	type Item=&'a T;
-	type IntoIter =std::slice::Iter<'a,T>;
+impl <'a,T> IntoIterator for &'a mut Matrix<T> where T:Clone{
+	type Item=&'a mut T;
+	type IntoIter =std::slice::IterMut<'a,T>;
 
+	//Padding has no storage, so it is materialized before handing out references
 	fn into_iter(self) -> Self::IntoIter{
-		self.inner.iter()
+		self.materialize();
+		self.inner.iter_mut()
 	}
 }
 
-impl <'a,T> IntoIterator for &'a mut Matrix<T>{
-	type Item=&'a mut T;
-	type IntoIter =std::slice::IterMut<'a,T>;
+impl <'a,T> Row<'a,T>{
+	pub fn get(&self,j:usize)->Option<&'a T>{
+		self.matrix.get(self.i,j)
+	}
+	pub fn index(&self)->usize{
+		self.i
+	}
+	pub fn len(&self)->usize{
+		self.matrix.get_cols()
+	}
+	pub fn is_empty(&self)->bool{
+		self.len() == 0
+	}
+	pub fn iter(&self)->impl Iterator<Item=&'a T> + 'a{
+		let (matrix,i) = (self.matrix,self.i);
+		(0..matrix.get_cols()).map(move |j| matrix.get(i,j).unwrap())
+	}
+}
 
-	fn into_iter(self) -> Self::IntoIter{
-		self.inner.iter_mut()
+impl <'a,T> Column<'a,T>{
+	pub fn get(&self,i:usize)->Option<&'a T>{
+		self.matrix.get(i,self.j)
+	}
+	pub fn index(&self)->usize{
+		self.j
+	}
+	pub fn len(&self)->usize{
+		self.matrix.get_rows()
+	}
+	pub fn is_empty(&self)->bool{
+		self.len() == 0
+	}
+	pub fn iter(&self)->impl Iterator<Item=&'a T> + 'a{
+		let (matrix,j) = (self.matrix,self.j);
+		(0..matrix.get_rows()).map(move |i| matrix.get(i,j).unwrap())
+	}
+}
+
+impl <'a,T> RowMut<'a,T>{
+	pub fn get(&self,j:usize)->Option<&T>{
+		self.cells.get(j)
+	}
+	pub fn get_mut(&mut self,j:usize)->Option<&mut T>{
+		self.cells.get_mut(j)
+	}
+	pub fn set(&mut self,j:usize,value:T){
+		self.cells[j] = value;
+	}
+	pub fn index(&self)->usize{
+		self.i
+	}
+	pub fn len(&self)->usize{
+		self.cells.len()
+	}
+	pub fn is_empty(&self)->bool{
+		self.len() == 0
+	}
+	pub fn iter(&self)->std::slice::Iter<'_,T>{
+		self.cells.iter()
+	}
+	pub fn iter_mut(&mut self)->std::slice::IterMut<'_,T>{
+		self.cells.iter_mut()
+	}
+}
+
+impl <'a,T> ColumnMut<'a,T>{
+	pub fn get(&self,i:usize)->Option<&T>{
+		self.cells.get(i).map(|cell| &**cell)
+	}
+	pub fn get_mut(&mut self,i:usize)->Option<&mut T>{
+		self.cells.get_mut(i).map(|cell| &mut **cell)
+	}
+	pub fn set(&mut self,i:usize,value:T){
+		*self.cells[i] = value;
+	}
+	pub fn index(&self)->usize{
+		self.j
+	}
+	pub fn len(&self)->usize{
+		self.cells.len()
+	}
+	pub fn is_empty(&self)->bool{
+		self.len() == 0
+	}
+	pub fn iter(&self)->impl Iterator<Item=&T>{
+		self.cells.iter().map(|cell| &**cell)
+	}
+	//Reborrowed into a new list, an iterator over the stored references would also capture 'a
+	pub fn iter_mut(&mut self)->impl Iterator<Item=&mut T>{
+		self.cells.iter_mut().map(|cell| &mut **cell).collect::<Vec<&mut T>>().into_iter()
+	}
+}
+
+impl <T> Matrix<T>{
+	pub fn rows(&self)->impl Iterator<Item=Row<'_,T>>{
+		(0..self.get_rows()).map(move |i| Row{matrix:self,i})
+	}
+	pub fn cols(&self)->impl Iterator<Item=Column<'_,T>>{
+		(0..self.get_cols()).map(move |j| Column{matrix:self,j})
+	}
+	//Mutable views materialize the padding first, like iter_mut
+	pub fn rows_mut(&mut self)->impl Iterator<Item=RowMut<'_,T>> where T:Clone{
+		self.materialize();
+		let columns = self.columns;
+		self.inner.chunks_mut(columns).enumerate().map(|(i,cells)| RowMut{cells,i})
+	}
+	pub fn cols_mut(&mut self)->impl Iterator<Item=ColumnMut<'_,T>> where T:Clone{
+		self.materialize();
+		let columns = self.columns;
+		let mut cols:Vec<Vec<&mut T>> = (0..columns).map(|_| Vec::with_capacity(self.rows)).collect();
+		for (n,cell) in self.inner.iter_mut().enumerate(){
+			cols[n%columns].push(cell);
+		}
+		cols.into_iter().enumerate().map(|(j,cells)| ColumnMut{cells,j})
+	}
+	pub fn row_mut(&mut self,i:usize)->RowMut<'_,T> where T:Clone{
+		assert!(i<self.virtual_rows,"row overflows matrix");
+		self.materialize();
+		let columns = self.columns;
+		RowMut{cells:&mut self.inner[i*columns..(i+1)*columns],i}
+	}
+	pub fn col_mut(&mut self,j:usize)->ColumnMut<'_,T> where T:Clone{
+		assert!(j<self.virtual_cols,"column overflows matrix");
+		self.materialize();
+		let columns = self.columns;
+		ColumnMut{cells:self.inner.iter_mut().skip(j).step_by(columns).collect(),j}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::matrix::Matrix;
+
+	#[test]
+	fn owned_iter_includes_padding(){
+		let matrix = Matrix::from_iter(2,2,vec![1,2,3,4]).expand(3,3);
+		let flat:Vec<i32> = matrix.into_iter().collect();
+		assert_eq!(flat,vec![1,2,0,3,4,0,0,0,0]);
+	}
+
+	#[test]
+	fn iter_mut_materializes_padding(){
+		let mut matrix = Matrix::from_iter(2,2,vec![1,2,3,4]).expand(2,3);
+		for element in matrix.iter_mut(){
+			*element+=10;
+		}
+		assert_eq!(matrix.get_orig_cols(),3);
+		let flat:Vec<&i32> = matrix.iter().collect();
+		assert_eq!(flat,vec![&11,&12,&10,&13,&14,&10]);
+	}
+
+	#[test]
+	fn mutable_rows_and_cols(){
+		let mut matrix = Matrix::from_iter(2,3,vec![1,2,3,4,5,6]).expand(3,3);
+		//Add the row index to every cell, the padded row too
+		for mut row in matrix.rows_mut(){
+			let i = row.index() as i32;
+			for cell in row.iter_mut(){
+				*cell+=i;
+			}
+		}
+		assert_eq!(matrix.get_inner(),&vec![1,2,3,5,6,7,2,2,2]);
+		for mut col in matrix.cols_mut(){
+			assert_eq!(col.len(),3);
+			let j = col.index() as i32;
+			col.set(1,j*10);
+			for cell in col.iter_mut().take(1){
+				*cell+=1;
+			}
+		}
+		assert_eq!(matrix.get_inner(),&vec![2,3,4,0,10,20,2,2,2]);
+		matrix.row_mut(1).set(0,7);
+		assert_eq!(matrix.col_mut(0).iter().collect::<Vec<&i32>>(),vec![&2,&7,&2]);
+		assert_eq!(matrix.get_orig_rows(),3);
+		let mut padded = Matrix::from_iter(1,2,vec![1,2]).expand(2,3);
+		padded.col_mut(2).set(1,9);
+		assert_eq!(padded.get(1,2),Some(&9));
+		assert_eq!(padded.get(0,2),Some(&0));
+	}
+
+	#[test]
+	fn rows_and_cols(){
+		let matrix = Matrix::from_iter(2,3,vec![1,2,3,4,5,6]).expand(2,4);
+		let rows:Vec<Vec<&i32>> = matrix.rows().map(|row| row.iter().collect()).collect();
+		assert_eq!(rows,vec![vec![&1,&2,&3,&0],vec![&4,&5,&6,&0]]);
+		let cols:Vec<i32> = matrix.cols().map(|col| col.iter().sum()).collect();
+		assert_eq!(cols,vec![5,7,9,0]);
+		assert_eq!(matrix.cols().nth(1).unwrap().get(1),Some(&5));
 	}
 }
-*/
\ No newline at end of file
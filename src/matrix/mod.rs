@@ -16,8 +16,8 @@ pub struct Matrix<T>{
 	columns:usize,
 	virtual_rows:usize,
 	virtual_cols:usize,
-	//TODO: default Option<T> so T doesnt have to have default trait
-	default:T
+	//Value of every cell that is not written, padding included
+	background:T
 }
 
 
@@ -27,6 +27,16 @@ impl <T> Matrix<T> where T:Default{
 	}
 	//if iterator has more elements than needed to fill the matrix, this method wont fail
 	pub fn from_iter(rows:usize,columns:usize,input:impl IntoIterator<Item=T>) -> Self{
+		Matrix::from_iter_with_background(rows,columns,T::default(),input)
+	}
+}
+
+impl <T> Matrix<T>{
+	pub fn with_background(rows:usize,columns:usize,background:T) -> Matrix<T> where T:Clone{
+		let fill = background.clone();
+		Matrix::from_iter_with_background(rows,columns,background,(0..).map(|_| fill.clone()))
+	}
+	pub fn from_iter_with_background(rows:usize,columns:usize,background:T,input:impl IntoIterator<Item=T>) -> Self{
 		assert!(rows>0 && columns>0);
 		Matrix{
 			inner:{
@@ -38,25 +48,24 @@ impl <T> Matrix<T> where T:Default{
 			columns,
 			virtual_rows:rows,
 			virtual_cols:columns,
-			default:T::default(),
+			background,
 		}
 	}
 
 	
-	pub fn iter<'a>(&'a self) ->iter::MatrixIterator<T>{
+	pub fn iter(&self) ->iter::MatrixIterator<'_,T>{
 		self.into_iter()
 	}
-	/*
-	pub fn iter_mut<'a>(&'a mut self) ->std::slice::IterMut<'a,T>{
+	//Row-major over the whole virtual matrix, padding is materialized first
+	pub fn iter_mut(&mut self) ->std::slice::IterMut<'_,T> where T:Clone{
 		self.into_iter()
 	}
-	 */
 
 	pub fn get(&self,i:usize,j:usize) ->Option<&T>{
 		if i < self.rows && j < self.columns {
 			return self.inner.get(i*self.columns+j)
 		}else if i < self.virtual_rows && j < self.virtual_cols{
-			return Some(&self.default);
+			return Some(&self.background);
 		}else{
 			return None;
 		}
@@ -86,22 +95,50 @@ impl <T> Matrix<T> where T:Default{
 	pub fn get_inner_mut(&mut self)-> &mut Vec<T>{
 		&mut self.inner
 	}
+	pub fn get_background(&self) -> &T{
+		&self.background
+	}
+	//Only unwritten cells (the padding) change value, stored cells are kept as they are
+	pub fn set_background(&mut self,background:T){
+		self.background = background;
+	}
 	
-	pub fn submatrix(&self,y:RangeInclusive<usize>,x:RangeInclusive<usize>)->Submatrix<T>{
-		if x.is_empty(){panic!("x range must not be empty")}
-		else if y.is_empty(){panic!("y range must not be empty")}
+	pub fn submatrix(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->Submatrix<'_,T>{
+		if rows.is_empty(){panic!("row range must not be empty")}
+		else if cols.is_empty(){panic!("column range must not be empty")}
 
-		//Virtual cols/rows are only incremented on expand method -> T must have default trait,
+		//Virtual cols/rows are only incremented on expand method and read as background,
 		//therefore, we can use virtual sizes for submatrix.
 
-		if *x.end() > self.virtual_cols-1{panic!("x range overflows matrix")}
-		else if *y.end() > self.virtual_rows-1{panic!("y range overflows matrix")}
+		if *rows.end() > self.virtual_rows-1{panic!("row range overflows matrix")}
+		else if *cols.end() > self.virtual_cols-1{panic!("column range overflows matrix")}
+
+		Submatrix::new(self,rows,cols)
+	}
 
-		Submatrix::new(self,y,x)
+	//Gives storage to the padded region, so the whole virtual matrix becomes real
+	pub fn materialize(&mut self) where T:Clone{
+		if self.rows == self.virtual_rows && self.columns == self.virtual_cols{
+			return;
+		}
+		let mut inner = Vec::with_capacity(self.virtual_rows*self.virtual_cols);
+		let mut old = std::mem::take(&mut self.inner).into_iter();
+		for i in 0..self.virtual_rows{
+			for j in 0..self.virtual_cols{
+				if i < self.rows && j < self.columns{
+					inner.push(old.next().unwrap());
+				}else{
+					inner.push(self.background.clone());
+				}
+			}
+		}
+		self.inner = inner;
+		self.rows = self.virtual_rows;
+		self.columns = self.virtual_cols;
 	}
 
 	//This method expands or shrinks matrix 
-	pub fn expand(mut self,rows:usize,columns:usize) -> Self where T: Default{
+	pub fn expand(mut self,rows:usize,columns:usize) -> Self{
 		if rows <self.rows || columns <self.columns{
 			panic!("Can't squeeze below current size");
 		};
@@ -112,7 +149,7 @@ impl <T> Matrix<T> where T:Default{
 }
 
 
-impl <T> Clone for Matrix<T> where T: Clone+Default{
+impl <T> Clone for Matrix<T> where T: Clone{
 	fn clone(&self) -> Self{
 		return Matrix{
 			inner:self.inner.clone(),
@@ -120,7 +157,7 @@ impl <T> Clone for Matrix<T> where T: Clone+Default{
 			columns:self.columns,
 			virtual_rows: self.virtual_rows,
 			virtual_cols: self.virtual_cols,
-			default:self.default.clone(),
+			background:self.background.clone(),
 		}
 	}
 }
@@ -174,7 +211,7 @@ mod tests {
 		assert_eq!(matrix.get_cols(),2);
 		assert_eq!(matrix.get_rows(),2);
 
-		let flat_matrix:Vec<&i32> = matrix.into_iter().collect();
+		let flat_matrix:Vec<&i32> = matrix.iter().collect();
 
 		assert_eq!(flat_matrix,expected.iter().collect::<Vec<&i32>>());
 	}
@@ -209,6 +246,15 @@ mod tests {
 		assert_eq!(sum,499999500000);
 	}
 
+	#[test]
+	fn explicit_background_padding(){
+		let matrix = Matrix::with_background(2,2,-9999).expand(4,4);
+		assert_eq!(matrix.get(1,1),Some(&-9999));
+		assert_eq!(matrix.get(3,3),Some(&-9999));
+		assert_eq!(matrix.get(4,0),None);
+		assert!(matrix.submatrix(2..=3,0..=3).all_eq());
+	}
+
 	#[test]
 	fn matrix_into_iter(){
 		let size = 3;
@@ -3,12 +3,11 @@ use std::ops::RangeInclusive;
 use std::fmt;
 use core::fmt::Display;
 
-//TODO: All_eq method for submatrix in default region -> check if index in default
-//region and return true
+//Window over a matrix. Row and column ranges are absolute positions of the matrix
 pub struct Submatrix<'a,T>{
 	matrix:&'a Matrix<T>,
-	x:RangeInclusive<usize>,
-	y:RangeInclusive<usize>
+	rows:RangeInclusive<usize>,
+	cols:RangeInclusive<usize>
 }
 
 pub struct SubmatrixIterator <'a,T>{
@@ -16,16 +15,29 @@ pub struct SubmatrixIterator <'a,T>{
 	current_i:usize,
 	current_j:usize,
 }
-impl <'a,T> Iterator for SubmatrixIterator<'a,T> where T:Default{
+
+//Yields ((i,j),&T) with (i,j) local to the submatrix, or absolute in the matrix
+pub struct IndexedSubmatrixIterator<'a,T>{
+	inner:SubmatrixIterator<'a,T>,
+	absolute:bool
+}
+
+impl <'a,T> Iterator for SubmatrixIterator<'a,T>{
 	type Item=&'a T;
 
 	fn next(&mut self) -> Option<&'a T>{
-		
+		self.next_indexed().map(|(_,value)| value)
+	}
+}
+
+impl <'a,T> SubmatrixIterator<'a,T>{
+	fn next_indexed(&mut self) -> Option<((usize,usize),&'a T)>{
 		if self.current_i >= self.submatrix.get_rows()
 		 || self.current_j >= self.submatrix.get_cols(){
-			
+
 			return None;
 		}
+		let position = (self.current_i,self.current_j);
 		match self.submatrix.get(self.current_i,self.current_j){
 			None=>None,
 			Some(n) => {
@@ -35,12 +47,26 @@ impl <'a,T> Iterator for SubmatrixIterator<'a,T> where T:Default{
 				}else{
 					self.current_j+=1;
 				}
-				Some(n)
+				Some((position,n))
 			}
 		}
 	}
 }
-impl <'a,T> IntoIterator for &'a Submatrix<'a,T> where T:Default{
+
+impl <'a,T> Iterator for IndexedSubmatrixIterator<'a,T>{
+	type Item=((usize,usize),&'a T);
+
+	fn next(&mut self) -> Option<Self::Item>{
+		let ((i,j),value) = self.inner.next_indexed()?;
+		if self.absolute{
+			Some((self.inner.submatrix.absolute(i,j),value))
+		}else{
+			Some(((i,j),value))
+		}
+	}
+}
+
+impl <'a,T> IntoIterator for &'a Submatrix<'a,T>{
 	type Item = &'a T;
 	type IntoIter = SubmatrixIterator<'a,T>;
 	fn into_iter(self) -> Self::IntoIter{
@@ -51,76 +77,83 @@ impl <'a,T> IntoIterator for &'a Submatrix<'a,T> where T:Default{
 		}
 	}
 }
-impl <'a,T> Submatrix<'a,T> where T: Default{
-	pub fn new(matrix:&'a Matrix<T>,y:RangeInclusive<usize>,x:RangeInclusive<usize>)->Self{
+impl <'a,T> Submatrix<'a,T>{
+	pub fn new(matrix:&'a Matrix<T>,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->Self{
 		Self{
 			matrix,
-			x,
-			y
+			rows,
+			cols
 		}
 	}
-	pub fn get(&self,i:usize,j:usize) -> Option<&T>{
-		if i > self.get_rows()-1 || j > self.get_cols()-1 {
+	pub fn get(&self,i:usize,j:usize) -> Option<&'a T>{
+		if i >= self.get_rows() || j >= self.get_cols() {
 			return None
 		}
-		
-		let absolute_i = self.x.start() + i;
-		let absolute_j = self.y.start()+j;
- 		self.matrix.get(absolute_i,absolute_j)
+		let (absolute_i,absolute_j) = self.absolute(i,j);
+		self.matrix.get(absolute_i,absolute_j)
+	}
+	//Position in the matrix of the local position (i,j)
+	pub fn absolute(&self,i:usize,j:usize)->(usize,usize){
+		(self.rows.start()+i,self.cols.start()+j)
 	}
 
-	//No funciona, iterador sobre toda la matriz
-	pub fn iter(&self)->SubmatrixIterator<T>{
+	//Row-major over the window
+	pub fn iter(&self)->SubmatrixIterator<'_,T>{
 		self.into_iter()
-
+	}
+	//Row-major over the window with local positions
+	pub fn indexed_iter(&self)->IndexedSubmatrixIterator<'_,T>{
+		IndexedSubmatrixIterator{
+			inner:self.into_iter(),
+			absolute:false
+		}
+	}
+	//Row-major over the window with positions in the underlying matrix
+	pub fn absolute_indexed_iter(&self)->IndexedSubmatrixIterator<'_,T>{
+		IndexedSubmatrixIterator{
+			inner:self.into_iter(),
+			absolute:true
+		}
 	}
 	pub fn get_rows(&self)->usize{
-		self.y.end() - self.y.start()+1
-
+		self.rows.end() - self.rows.start()+1
 	}
 	pub fn get_cols(&self)->usize{
-		self.x.end() - self.x.start()+1
+		self.cols.end() - self.cols.start()+1
+	}
+	pub fn get_row_range(&self)->&RangeInclusive<usize>{
+		&self.rows
+	}
+	pub fn get_col_range(&self)->&RangeInclusive<usize>{
+		&self.cols
 	}
 	//Ranges are offset from submatrix, not from original matrix
-	pub fn submatrix(&self,y:RangeInclusive<usize>,x:RangeInclusive<usize>)->Submatrix<'a,T>{
-		if x.is_empty(){panic!("x range must not be empty")}
-		else if y.is_empty(){panic!("y range must not be empty")}
+	pub fn submatrix(&self,rows:RangeInclusive<usize>,cols:RangeInclusive<usize>)->Submatrix<'a,T>{
+		if rows.is_empty(){panic!("row range must not be empty")}
+		else if cols.is_empty(){panic!("column range must not be empty")}
 
-		if *x.end() > self.get_cols()-1{panic!("x range overflows matrix")}
-		else if *y.end() > self.get_rows()-1{panic!("y range overflows matrix")}
+		if *rows.end() > self.get_rows()-1{panic!("row range overflows matrix")}
+		else if *cols.end() > self.get_cols()-1{panic!("column range overflows matrix")}
 
-		let x_elems = x.end() - x.start()+1;
-		let x_absolute_start = self.x.start()+x.start();
-		let absolute_x = x_absolute_start..=x_absolute_start+x_elems-1;
+		let absolute_rows = self.rows.start()+rows.start()..=self.rows.start()+rows.end();
+		let absolute_cols = self.cols.start()+cols.start()..=self.cols.start()+cols.end();
 
-		let y_elems = y.end() - y.start()+1;
-		let y_absolute_start = self.y.start()+y.start();
-		let absolute_y  = y_absolute_start..=y_absolute_start+y_elems-1;
-		
-		Submatrix::new(self.matrix,absolute_y,absolute_x)
+		Submatrix::new(self.matrix,absolute_rows,absolute_cols)
 	}
 	pub fn elems(&self)->usize{
-		let x_elems = self.x.end() - self.x.start()+1;
-		let y_elems = self.y.end() - self.y.start()+1;
-		return x_elems * y_elems
+		self.get_rows() * self.get_cols()
 	}
 	pub fn all_eq(&self)->bool where T:Eq{
-		//If all elements are default
-		if *self.x.start()>=self.matrix.get_orig_cols() && *self.y.start()>=self.matrix.get_orig_rows(){
+		//If all elements are background (padding)
+		if *self.rows.start()>=self.matrix.get_orig_rows() || *self.cols.start()>=self.matrix.get_orig_cols(){
 			return true;
 		}
-		let first = self.matrix.get(*self.x.start(),*self.y.start()).unwrap();
-		let mut acc = true;
-		for i in self.x.clone(){
-			for j in self.y.clone(){
-				acc &= *first == *self.matrix.get(i,j).unwrap();
-			}
-		}
-		return acc;
+		let first = self.get(0,0).unwrap();
+		self.iter().all(|element| element == first)
 	}
 }
 
-impl <'a,T> Display for Submatrix<'a,T> where T:Display+Default {
+impl <'a,T> Display for Submatrix<'a,T> where T:Display {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>)-> fmt::Result {
 		let mut output = String::new();
 		for i in 0..self.get_rows(){
@@ -161,15 +194,46 @@ mod tests{
 	fn submatrix_iter(){
 		let size=4;
 		let matrix = Matrix::from_iter(size,size,0..size*size);
-		let submatrix = matrix.submatrix(0..=1,0..=2);
+		let submatrix = matrix.submatrix(1..=2,0..=2);
+
+		let elements:Vec<&usize> = submatrix.iter().collect();
+		assert_eq!(elements,vec![&4,&5,&6,&8,&9,&10]);
+	}
 
-		println!("Submatrix: ");
-		println!("{}",submatrix);
-		println!("Iterator:");
-		for element in submatrix.iter() {
-			print!("{} ",element);
+	#[test]
+	fn submatrix_get_non_square_offset(){
+		let matrix = Matrix::from_iter(3,5,0..15);
+		let submatrix = matrix.submatrix(1..=2,3..=4);
+		assert_eq!(submatrix.get(0,0),Some(&8));
+		assert_eq!(submatrix.get(1,1),Some(&14));
+		assert_eq!(submatrix.get(2,0),None);
+		let subsubmatrix = submatrix.submatrix(1..=1,0..=1);
+		assert_eq!(subsubmatrix.iter().collect::<Vec<_>>(),vec![&13,&14]);
+		assert_eq!(subsubmatrix.get_row_range(),&(2..=2));
+		assert_eq!(subsubmatrix.get_col_range(),&(3..=4));
+	}
+
+	#[test]
+	fn submatrix_indexed_iter(){
+		let matrix = Matrix::from_iter(3,4,0..12);
+		let submatrix = matrix.submatrix(1..=2,2..=3);
+		let local:Vec<((usize,usize),&usize)> = submatrix.indexed_iter().collect();
+		assert_eq!(local,vec![((0,0),&6),((0,1),&7),((1,0),&10),((1,1),&11)]);
+		let absolute:Vec<((usize,usize),&usize)> = submatrix.absolute_indexed_iter().collect();
+		assert_eq!(absolute,vec![((1,2),&6),((1,3),&7),((2,2),&10),((2,3),&11)]);
+		for ((i,j),value) in submatrix.absolute_indexed_iter(){
+			assert_eq!(matrix.get(i,j),Some(value));
 		}
-		println!("");
 	}
-}
 
+	#[test]
+	fn submatrix_all_eq(){
+		let matrix = Matrix::from_iter(2,4,vec![1,1,0,0, 1,1,0,3]).expand(4,4);
+		assert!(matrix.submatrix(0..=1,0..=1).all_eq());
+		assert!(!matrix.submatrix(0..=1,2..=3).all_eq());
+		assert!(matrix.submatrix(0..=0,2..=3).all_eq());
+		//Padding rows only
+		assert!(matrix.submatrix(2..=3,0..=3).all_eq());
+		assert!(!matrix.submatrix(1..=2,2..=3).all_eq());
+	}
+}
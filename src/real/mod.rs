@@ -0,0 +1,40 @@
+use std::hash::{Hash, Hasher};
+
+//Real values compared bit by bit, so they can live in a K2tree. Both zeros are the same value.
+#[derive(Debug,Clone,Copy)]
+pub struct Real(pub f64);
+
+impl Real{
+	fn bits(&self)->u64{
+		if self.0 == 0.0 {0} else {self.0.to_bits()}
+	}
+}
+
+impl PartialEq for Real{
+	fn eq(&self,other:&Self)->bool{
+		self.bits() == other.bits()
+	}
+}
+
+impl Eq for Real{}
+
+impl Hash for Real{
+	fn hash<H:Hasher>(&self,state:&mut H){
+		self.bits().hash(state);
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use std::collections::HashSet;
+	use super::Real;
+
+	#[test]
+	fn bitwise_equality(){
+		assert_eq!(Real(2.5),Real(2.5));
+		assert_ne!(Real(1.0),Real(-1.0));
+		assert_eq!(Real(f64::NAN),Real(f64::NAN));
+		let values:HashSet<Real> = [Real(0.0),Real(-0.0),Real(3.0)].into_iter().collect();
+		assert_eq!(values.len(),2);
+	}
+}
@@ -0,0 +1,328 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Serialize, Deserialize};
+
+use crate::k2tree::K2tree;
+
+//How node ids are renumbered before building the tree
+#[derive(Debug,Clone,PartialEq)]
+pub enum Reordering<'a>{
+	//Breadth first visit over the undirected graph, components in id order
+	Bfs,
+	//Highest total degree first
+	Degree,
+	//Lexicographic order of the URLs with the host reversed (www.a.com/x -> com.a.www/x),
+	//which puts the pages of a site and its subdomains together. One label per node.
+	Url(&'a [String]),
+	//Label propagation communities, one after another
+	Community{iterations:usize}
+}
+
+//Bijection between original ids and tree ids
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
+pub struct Permutation{
+	//new_of[original] = tree id
+	new_of:Vec<usize>,
+	//old_of[tree id] = original
+	old_of:Vec<usize>
+}
+
+impl Permutation{
+	pub fn identity(nodes:usize)->Self{
+		Permutation{
+			new_of:(0..nodes).collect(),
+			old_of:(0..nodes).collect()
+		}
+	}
+	//order lists the original ids in their new order
+	pub fn from_order(order:Vec<usize>)->Self{
+		let mut new_of = vec![usize::MAX;order.len()];
+		for (new,&old) in order.iter().enumerate(){
+			assert!(old<order.len() && new_of[old] == usize::MAX,"order is not a permutation");
+			new_of[old] = new;
+		}
+		Permutation{
+			new_of,
+			old_of:order
+		}
+	}
+	pub fn compute(nodes:usize,edges:&[(usize,usize)],reordering:&Reordering)->Self{
+		let order = match reordering{
+			Reordering::Bfs => bfs_order(&undirected(nodes,edges)),
+			Reordering::Degree => degree_order(nodes,edges),
+			Reordering::Url(labels) => url_order(labels,nodes),
+			Reordering::Community{iterations} => community_order(&undirected(nodes,edges),*iterations)
+		};
+		Self::from_order(order)
+	}
+	pub fn apply(&self,original:usize)->usize{
+		self.new_of[original]
+	}
+	pub fn inverse(&self,new:usize)->usize{
+		self.old_of[new]
+	}
+	pub fn len(&self)->usize{
+		self.new_of.len()
+	}
+	pub fn is_empty(&self)->bool{
+		self.new_of.is_empty()
+	}
+}
+
+fn undirected(nodes:usize,edges:&[(usize,usize)])->Vec<Vec<usize>>{
+	let mut adjacency = vec![Vec::new();nodes];
+	for &(i,j) in edges{
+		if i != j{
+			adjacency[i].push(j);
+			adjacency[j].push(i);
+		}
+	}
+	for neighbours in adjacency.iter_mut(){
+		neighbours.sort_unstable();
+		neighbours.dedup();
+	}
+	adjacency
+}
+
+fn bfs_order(adjacency:&[Vec<usize>])->Vec<usize>{
+	let mut visited = vec![false;adjacency.len()];
+	let mut order = Vec::with_capacity(adjacency.len());
+	let mut queue = VecDeque::new();
+	for root in 0..adjacency.len(){
+		if visited[root]{
+			continue;
+		}
+		visited[root] = true;
+		queue.push_back(root);
+		while let Some(node) = queue.pop_front(){
+			order.push(node);
+			for &neighbour in &adjacency[node]{
+				if !visited[neighbour]{
+					visited[neighbour] = true;
+					queue.push_back(neighbour);
+				}
+			}
+		}
+	}
+	order
+}
+
+fn degree_order(nodes:usize,edges:&[(usize,usize)])->Vec<usize>{
+	let mut degree = vec![0usize;nodes];
+	for &(i,j) in edges{
+		degree[i]+=1;
+		//A self loop is one edge of its node
+		if i != j{
+			degree[j]+=1;
+		}
+	}
+	let mut order:Vec<usize> = (0..nodes).collect();
+	order.sort_by_key(|&node| (std::cmp::Reverse(degree[node]),node));
+	order
+}
+
+//Reversed host and path, compared host first so a site stays ahead of its subdomains
+fn reversed_host(url:&str)->(String,String){
+	let rest = match url.find("://"){
+		Some(e) => &url[e+3..],
+		None => url
+	};
+	let (host,path) = match rest.find('/'){
+		Some(e) => rest.split_at(e),
+		None => (rest,"")
+	};
+	let mut parts:Vec<&str> = host.split('.').collect();
+	parts.reverse();
+	(parts.join("."),String::from(path))
+}
+
+fn url_order(labels:&[String],nodes:usize)->Vec<usize>{
+	assert_eq!(labels.len(),nodes,"one url per node is needed");
+	let keys:Vec<(String,String)> = labels.iter().map(|url| reversed_host(url)).collect();
+	let mut order:Vec<usize> = (0..nodes).collect();
+	order.sort_by(|&a,&b| keys[a].cmp(&keys[b]).then(a.cmp(&b)));
+	order
+}
+
+//Each node takes the most common label among its neighbours (smallest label on ties) for a
+//fixed number of rounds. Communities are laid out in order of their smallest node, and each
+//one is visited breadth first so that neighbours stay close.
+fn community_order(adjacency:&[Vec<usize>],iterations:usize)->Vec<usize>{
+	let nodes = adjacency.len();
+	let mut label:Vec<usize> = (0..nodes).collect();
+	for _ in 0..iterations{
+		let mut changed = false;
+		for node in 0..nodes{
+			if adjacency[node].is_empty(){
+				continue;
+			}
+			let mut counts:HashMap<usize,usize> = HashMap::new();
+			for &neighbour in &adjacency[node]{
+				*counts.entry(label[neighbour]).or_insert(0)+=1;
+			}
+			let best = counts.into_iter().max_by_key(|&(l,count)| (count,std::cmp::Reverse(l))).unwrap().0;
+			if best != label[node]{
+				label[node] = best;
+				changed = true;
+			}
+		}
+		if !changed{
+			break;
+		}
+	}
+	let mut communities:Vec<Vec<usize>> = Vec::new();
+	let mut index:HashMap<usize,usize> = HashMap::new();
+	for (node,&community) in label.iter().enumerate(){
+		let e = *index.entry(community).or_insert_with(|| {
+			communities.push(Vec::new());
+			communities.len()-1
+		});
+		communities[e].push(node);
+	}
+	let mut order = Vec::with_capacity(nodes);
+	let mut visited = vec![false;nodes];
+	for community in communities{
+		for &root in &community{
+			if visited[root]{
+				continue;
+			}
+			visited[root] = true;
+			let mut queue = VecDeque::from(vec![root]);
+			while let Some(node) = queue.pop_front(){
+				order.push(node);
+				for &neighbour in &adjacency[node]{
+					if !visited[neighbour] && label[neighbour] == label[root]{
+						visited[neighbour] = true;
+						queue.push_back(neighbour);
+					}
+				}
+			}
+		}
+	}
+	order
+}
+
+//Tree built over renumbered nodes. Queries take and return original ids.
+#[derive(Serialize,Deserialize,Debug)]
+#[serde(bound(deserialize = "T: Deserialize<'de> + Default"))]
+pub struct ReorderedK2tree<T> where T:Clone{
+	tree:K2tree<T>,
+	permutation:Permutation
+}
+
+impl <T> ReorderedK2tree<T> where T:Eq + Clone{
+	pub fn new(tree:K2tree<T>,permutation:Permutation)->Self{
+		assert!(tree.get_rows() == permutation.len() && tree.get_cols() == permutation.len(),
+			"permutation does not match the tree");
+		ReorderedK2tree{
+			tree,
+			permutation
+		}
+	}
+	//Square graph of the given nodes, renumbered with the strategy before building
+	pub fn from_triplets(nodes:usize,k:usize,background:T,
+		triplets:impl IntoIterator<Item=(usize,usize,T)>,reordering:&Reordering)->Self{
+		let triplets:Vec<(usize,usize,T)> = triplets.into_iter().collect();
+		let edges:Vec<(usize,usize)> = triplets.iter().map(|(i,j,_)| (*i,*j)).collect();
+		let permutation = Permutation::compute(nodes,&edges,reordering);
+		let renumbered = triplets.into_iter().map(|(i,j,value)| (permutation.apply(i),permutation.apply(j),value));
+		let tree = K2tree::from_triplets(nodes,nodes,k,background,renumbered);
+		Self::new(tree,permutation)
+	}
+	pub fn get(&self,i:usize,j:usize)->Option<&T>{
+		self.tree.get(self.permutation.apply(i),self.permutation.apply(j))
+	}
+	//Non-background cells of row i, sorted by original column
+	pub fn row(&self,i:usize)->Vec<(usize,&T)>{
+		let mut row:Vec<(usize,&T)> = self.tree.row(self.permutation.apply(i)).into_iter()
+			.map(|(j,value)| (self.permutation.inverse(j),value))
+			.collect();
+		row.sort_by_key(|&(j,_)| j);
+		row
+	}
+	//Non-background cells of column j, sorted by original row
+	pub fn column(&self,j:usize)->Vec<(usize,&T)>{
+		let mut column:Vec<(usize,&T)> = self.tree.column(self.permutation.apply(j)).into_iter()
+			.map(|(i,value)| (self.permutation.inverse(i),value))
+			.collect();
+		column.sort_by_key(|&(i,_)| i);
+		column
+	}
+	pub fn get_tree(&self)->&K2tree<T>{
+		&self.tree
+	}
+	pub fn get_permutation(&self)->&Permutation{
+		&self.permutation
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::k2tree::K2tree;
+	use super::{Permutation,Reordering,ReorderedK2tree,reversed_host};
+
+	//Two 4-cliques whose ids are interleaved (even and odd nodes)
+	fn interleaved()->Vec<(usize,usize)>{
+		let mut edges = Vec::new();
+		for a in 0..4{
+			for b in 0..4{
+				if a != b{
+					edges.push((2*a,2*b));
+					edges.push((2*a+1,2*b+1));
+				}
+			}
+		}
+		edges
+	}
+
+	fn check_queries(reordered:&ReorderedK2tree<bool>,edges:&[(usize,usize)],nodes:usize){
+		let original = K2tree::from_triplets(nodes,nodes,2,false,edges.iter().map(|&(i,j)| (i,j,true)));
+		for i in 0..nodes{
+			for j in 0..nodes{
+				assert_eq!(reordered.get(i,j),original.get(i,j));
+			}
+			assert_eq!(reordered.row(i),original.row(i));
+			assert_eq!(reordered.column(i),original.column(i));
+		}
+	}
+
+	#[test]
+	fn strategies_keep_original_ids(){
+		let edges = interleaved();
+		let urls:Vec<String> = (0..8).map(|e| format!("http://{}.example.com/{}",["a","b"][e%2],e)).collect();
+		for reordering in [Reordering::Bfs,Reordering::Degree,Reordering::Url(&urls),Reordering::Community{iterations:10}]{
+			let reordered = ReorderedK2tree::from_triplets(8,2,false,edges.iter().map(|&(i,j)| (i,j,true)),&reordering);
+			check_queries(&reordered,&edges,8);
+		}
+	}
+
+	#[test]
+	fn grouping_improves_compression(){
+		let edges = interleaved();
+		let original = K2tree::from_triplets(8,8,2,false,edges.iter().map(|&(i,j)| (i,j,true)));
+		for reordering in [Reordering::Bfs,Reordering::Community{iterations:10}]{
+			let reordered = ReorderedK2tree::from_triplets(8,2,false,edges.iter().map(|&(i,j)| (i,j,true)),&reordering);
+			let tree = reordered.get_tree();
+			assert!(tree.get_leaf().len() + tree.get_nodes().len() < original.get_leaf().len() + original.get_nodes().len());
+		}
+	}
+
+	#[test]
+	fn orders(){
+		let edges = vec![(0,3),(3,1),(2,2)];
+		let bfs = Permutation::compute(4,&edges,&Reordering::Bfs);
+		assert_eq!((0..4).map(|e| bfs.inverse(e)).collect::<Vec<_>>(),vec![0,3,1,2]);
+		let degree = Permutation::compute(4,&edges,&Reordering::Degree);
+		assert_eq!((0..4).map(|e| degree.inverse(e)).collect::<Vec<_>>(),vec![3,0,1,2]);
+		//Two self loops weigh as much as two edges, not four
+		let looped = Permutation::compute(4,&[(0,0),(0,0),(1,2),(2,1),(1,3)],&Reordering::Degree);
+		assert_eq!((0..4).map(|e| looped.inverse(e)).collect::<Vec<_>>(),vec![1,0,2,3]);
+		for e in 0..4{
+			assert_eq!(degree.apply(degree.inverse(e)),e);
+		}
+		assert_eq!(reversed_host("https://www.example.com/a/b"),(String::from("com.example.www"),String::from("/a/b")));
+		let urls:Vec<String> = vec!["b.org/x","a.com/z","www.a.com/y","a.com/a"].into_iter().map(String::from).collect();
+		let url = Permutation::compute(4,&[],&Reordering::Url(&urls));
+		assert_eq!((0..4).map(|e| url.inverse(e)).collect::<Vec<_>>(),vec![3,1,2,0]);
+	}
+}
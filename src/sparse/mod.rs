@@ -0,0 +1,233 @@
+use crate::matrix::Matrix;
+
+//Coordinate (triplet) representation. Cells not listed hold the background value.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Coo<T>{
+	rows:usize,
+	columns:usize,
+	background:T,
+	row_idx:Vec<usize>,
+	col_idx:Vec<usize>,
+	values:Vec<T>
+}
+
+//Compressed sparse row representation. Cells not listed hold the background value.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Csr<T>{
+	rows:usize,
+	columns:usize,
+	background:T,
+	//row_ptr[i]..row_ptr[i+1] are the entries of row i
+	row_ptr:Vec<usize>,
+	col_idx:Vec<usize>,
+	values:Vec<T>
+}
+
+impl <T> Coo<T>{
+	pub fn new(rows:usize,columns:usize,background:T)->Self{
+		Coo{
+			rows,
+			columns,
+			background,
+			row_idx:Vec::new(),
+			col_idx:Vec::new(),
+			values:Vec::new()
+		}
+	}
+	pub fn from_triplets(rows:usize,columns:usize,background:T,triplets:impl IntoIterator<Item=(usize,usize,T)>)->Self{
+		let mut coo = Self::new(rows,columns,background);
+		for (i,j,value) in triplets{
+			coo.push(i,j,value);
+		}
+		coo
+	}
+	pub fn push(&mut self,i:usize,j:usize,value:T){
+		assert!(i<self.rows && j<self.columns,"position overflows matrix");
+		self.row_idx.push(i);
+		self.col_idx.push(j);
+		self.values.push(value);
+	}
+	pub fn iter(&self)->impl Iterator<Item=(usize,usize,&T)>{
+		self.row_idx.iter().zip(self.col_idx.iter()).zip(self.values.iter())
+			.map(|((i,j),value)| (*i,*j,value))
+	}
+	pub fn len(&self)->usize{
+		self.values.len()
+	}
+	pub fn is_empty(&self)->bool{
+		self.values.is_empty()
+	}
+	pub fn get_rows(&self)->usize{
+		self.rows
+	}
+	pub fn get_cols(&self)->usize{
+		self.columns
+	}
+	pub fn get_background(&self)->&T{
+		&self.background
+	}
+	pub fn get_row_idx(&self)->&Vec<usize>{
+		&self.row_idx
+	}
+	pub fn get_col_idx(&self)->&Vec<usize>{
+		&self.col_idx
+	}
+	pub fn get_values(&self)->&Vec<T>{
+		&self.values
+	}
+	pub fn into_triplets(self)->impl Iterator<Item=(usize,usize,T)>{
+		self.row_idx.into_iter().zip(self.col_idx).zip(self.values)
+			.map(|((i,j),value)| (i,j,value))
+	}
+}
+
+impl <T> Coo<T> where T:Eq + Clone{
+	//Entries equal to the background of the matrix are dropped
+	pub fn from_matrix(matrix:&Matrix<T>)->Self{
+		let mut coo = Self::new(matrix.get_rows(),matrix.get_cols(),matrix.get_background().clone());
+		for i in 0..matrix.get_rows(){
+			for j in 0..matrix.get_cols(){
+				let value = matrix.get(i,j).unwrap();
+				if *value != coo.background{
+					coo.push(i,j,value.clone());
+				}
+			}
+		}
+		coo
+	}
+	pub fn to_matrix(&self)->Matrix<T>{
+		let mut matrix = Matrix::with_background(self.rows,self.columns,self.background.clone());
+		for (i,j,value) in self.iter(){
+			matrix.set(i,j,value.clone());
+		}
+		matrix
+	}
+	//Duplicated positions keep the last value pushed
+	pub fn to_csr(&self)->Csr<T>{
+		let mut order:Vec<usize> = (0..self.len()).collect();
+		order.sort_by_key(|&e| (self.row_idx[e],self.col_idx[e]));
+		let mut row_ptr = vec![0;self.rows+1];
+		let mut col_idx:Vec<usize> = Vec::with_capacity(self.len());
+		let mut values:Vec<T> = Vec::with_capacity(self.len());
+		let mut last:Option<(usize,usize)> = None;
+		for e in order{
+			let position = (self.row_idx[e],self.col_idx[e]);
+			if last == Some(position){
+				*values.last_mut().unwrap() = self.values[e].clone();
+				continue;
+			}
+			last = Some(position);
+			row_ptr[position.0+1]+=1;
+			col_idx.push(position.1);
+			values.push(self.values[e].clone());
+		}
+		for i in 0..self.rows{
+			row_ptr[i+1]+=row_ptr[i];
+		}
+		Csr{
+			rows:self.rows,
+			columns:self.columns,
+			background:self.background.clone(),
+			row_ptr,
+			col_idx,
+			values
+		}
+	}
+}
+
+impl <T> Csr<T>{
+	pub fn new(rows:usize,columns:usize,background:T,row_ptr:Vec<usize>,col_idx:Vec<usize>,values:Vec<T>)->Self{
+		assert_eq!(row_ptr.len(),rows+1,"row_ptr must have rows+1 entries");
+		assert_eq!(col_idx.len(),values.len(),"col_idx and values must have the same length");
+		assert_eq!(row_ptr[0],0,"row_ptr must start at 0");
+		assert_eq!(row_ptr[rows],values.len(),"row_ptr must end at the number of entries");
+		assert!(row_ptr.windows(2).all(|w| w[0]<=w[1]),"row_ptr must be non decreasing");
+		assert!(col_idx.iter().all(|&j| j<columns),"column index overflows matrix");
+		Csr{
+			rows,
+			columns,
+			background,
+			row_ptr,
+			col_idx,
+			values
+		}
+	}
+	//Entries of row i as (column,value)
+	pub fn row(&self,i:usize)->impl Iterator<Item=(usize,&T)>{
+		let range = self.row_ptr[i]..self.row_ptr[i+1];
+		self.col_idx[range.clone()].iter().copied().zip(self.values[range].iter())
+	}
+	pub fn iter(&self)->impl Iterator<Item=(usize,usize,&T)>{
+		(0..self.rows).flat_map(move |i| self.row(i).map(move |(j,value)| (i,j,value)))
+	}
+	pub fn len(&self)->usize{
+		self.values.len()
+	}
+	pub fn is_empty(&self)->bool{
+		self.values.is_empty()
+	}
+	pub fn get_rows(&self)->usize{
+		self.rows
+	}
+	pub fn get_cols(&self)->usize{
+		self.columns
+	}
+	pub fn get_background(&self)->&T{
+		&self.background
+	}
+	pub fn get_row_ptr(&self)->&Vec<usize>{
+		&self.row_ptr
+	}
+	pub fn get_col_idx(&self)->&Vec<usize>{
+		&self.col_idx
+	}
+	pub fn get_values(&self)->&Vec<T>{
+		&self.values
+	}
+}
+
+impl <T> Csr<T> where T:Eq + Clone{
+	pub fn from_matrix(matrix:&Matrix<T>)->Self{
+		Coo::from_matrix(matrix).to_csr()
+	}
+	pub fn to_matrix(&self)->Matrix<T>{
+		self.to_coo().to_matrix()
+	}
+	pub fn to_coo(&self)->Coo<T>{
+		Coo::from_triplets(self.rows,self.columns,self.background.clone(),
+			self.iter().map(|(i,j,value)| (i,j,value.clone())))
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::matrix::Matrix;
+	use super::{Coo,Csr};
+
+	#[test]
+	fn matrix_roundtrip(){
+		let matrix = Matrix::from_iter(3,4,vec![0,2,0,0, 0,0,0,0, 5,0,0,1]);
+		let csr = Csr::from_matrix(&matrix);
+		assert_eq!(csr.get_row_ptr(),&vec![0,1,1,3]);
+		assert_eq!(csr.get_col_idx(),&vec![1,0,3]);
+		assert_eq!(csr.get_values(),&vec![2,5,1]);
+		assert_eq!(csr.to_matrix().get_inner(),matrix.get_inner());
+	}
+
+	#[test]
+	fn explicit_background(){
+		let matrix = Matrix::from_iter_with_background(2,2,-9999,vec![-9999,3,-9999,-9999]);
+		let coo = Coo::from_matrix(&matrix);
+		assert_eq!(coo.len(),1);
+		assert_eq!(coo.iter().collect::<Vec<_>>(),vec![(0,1,&3)]);
+		assert_eq!(coo.to_matrix().get_inner(),matrix.get_inner());
+	}
+
+	#[test]
+	fn coo_to_csr_sorts_and_deduplicates(){
+		let coo = Coo::from_triplets(3,3,0,vec![(2,0,1),(0,2,4),(0,1,3),(2,0,9)]);
+		let csr = coo.to_csr();
+		assert_eq!(csr.iter().collect::<Vec<_>>(),vec![(0,1,&3),(0,2,&4),(2,0,&9)]);
+		assert_eq!(csr.to_coo().to_csr(),csr);
+	}
+}
@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use super::TripleStore;
+use super::ntriples::next_term;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum PatternTerm{
+	Var(String),
+	//Term in N-Triples syntax
+	Const(String)
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct TriplePattern{
+	pub subject:PatternTerm,
+	pub predicate:PatternTerm,
+	pub object:PatternTerm
+}
+
+//Conjunction of triple patterns, written as "?x <p1> ?y . ?y <p2> <c>". Variables start with ?,
+//everything else is an N-Triples term. A predicate variable can not also be a subject or object.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Bgp{
+	patterns:Vec<TriplePattern>
+}
+
+fn parse_term(input:&str)->Result<(PatternTerm,&str),String>{
+	let input = input.trim_start();
+	if let Some(rest) = input.strip_prefix('?'){
+		let end = rest.find(|c:char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+		if end == 0{
+			return Err(String::from("empty variable name"));
+		}
+		return Ok((PatternTerm::Var(rest[..end].to_string()),&rest[end..]));
+	}
+	let (term,rest) = next_term(input)?;
+	Ok((PatternTerm::Const(term.to_string()),rest))
+}
+
+impl Bgp{
+	pub fn new(patterns:Vec<TriplePattern>)->Result<Self,String>{
+		if patterns.is_empty(){
+			return Err(String::from("no triple patterns"));
+		}
+		let var = |term:&PatternTerm| match term{
+			PatternTerm::Var(name) => Some(name.clone()),
+			PatternTerm::Const(_) => None
+		};
+		for pattern in &patterns{
+			if let Some(name) = var(&pattern.predicate){
+				let clash = patterns.iter().any(|other| var(&other.subject).as_ref() == Some(&name) || var(&other.object).as_ref() == Some(&name));
+				if clash{
+					return Err(format!("?{} is used as predicate and as node",name));
+				}
+			}
+		}
+		Ok(Bgp{patterns})
+	}
+	pub fn parse(input:&str)->Result<Self,String>{
+		let mut patterns = Vec::new();
+		let mut rest = input.trim();
+		while !rest.is_empty(){
+			let (subject,tail) = parse_term(rest)?;
+			let (predicate,tail) = parse_term(tail)?;
+			let (object,tail) = parse_term(tail)?;
+			patterns.push(TriplePattern{subject,predicate,object});
+			let tail = tail.trim_start();
+			rest = match tail.strip_prefix('.'){
+				Some(tail) => tail.trim_start(),
+				None if tail.is_empty() => tail,
+				None => return Err(format!("expected . before {}",tail))
+			};
+		}
+		Self::new(patterns)
+	}
+	pub fn get_patterns(&self)->&Vec<TriplePattern>{
+		&self.patterns
+	}
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum JoinStrategy{
+	//Every pattern is solved on its own (constants only) and the results are hash joined
+	Independent,
+	//The bindings found so far are substituted into the next pattern, which becomes a
+	//get, row or column query
+	Chain,
+	//Two sides sharing one variable split its domain together and drop every range empty on
+	//either side (range queries), binding it only at single values
+	Interactive
+}
+
+//Variables in order of first appearance and one row of ids per solution, sorted. Predicate
+//variables hold predicate ids, the rest term ids.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Solutions{
+	variables:Vec<String>,
+	predicate:Vec<bool>,
+	rows:Vec<Vec<usize>>
+}
+
+impl Solutions{
+	pub fn get_variables(&self)->&Vec<String>{
+		&self.variables
+	}
+	pub fn get_rows(&self)->&Vec<Vec<usize>>{
+		&self.rows
+	}
+	pub fn len(&self)->usize{
+		self.rows.len()
+	}
+	pub fn is_empty(&self)->bool{
+		self.rows.is_empty()
+	}
+	pub fn to_labels<'a>(&self,store:&'a TripleStore)->Vec<Vec<&'a str>>{
+		self.rows.iter().map(|row| row.iter().zip(self.predicate.iter())
+			.map(|(&id,&predicate)| if predicate {store.predicate(id)} else {store.term(id)})
+			.collect()).collect()
+	}
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum Slot{
+	Const(usize),
+	Var(usize)
+}
+
+#[derive(Debug,Clone,Copy)]
+struct Pattern{
+	s:Slot,
+	p:Slot,
+	o:Slot
+}
+
+type Row = Vec<Option<usize>>;
+
+impl Pattern{
+	fn slots(&self)->[Slot;3]{
+		[self.s,self.p,self.o]
+	}
+	fn vars(&self)->Vec<usize>{
+		self.slots().iter().filter_map(|slot| match slot {Slot::Var(v) => Some(*v), _ => None}).collect()
+	}
+	fn constants(&self)->usize{
+		self.slots().iter().filter(|slot| matches!(slot,Slot::Const(_))).count()
+	}
+	fn value(slot:Slot,row:&Row)->Option<usize>{
+		match slot{
+			Slot::Const(id) => Some(id),
+			Slot::Var(v) => row[v]
+		}
+	}
+	//Triples matching the pattern with the bindings of row substituted
+	fn solve(&self,store:&TripleStore,row:&Row)->Vec<(usize,usize,usize)>{
+		store.matches(Self::value(self.s,row),Self::value(self.p,row),Self::value(self.o,row))
+	}
+	//row plus the bindings of triple, None if they disagree (a variable repeated in the pattern)
+	fn extend(&self,row:&Row,triple:(usize,usize,usize))->Option<Row>{
+		let mut row = row.clone();
+		for (slot,value) in self.slots().iter().zip([triple.0,triple.1,triple.2]){
+			if let Slot::Var(v) = slot{
+				match row[*v]{
+					Some(bound) if bound != value => return None,
+					_ => row[*v] = Some(value)
+				}
+			}
+		}
+		Some(row)
+	}
+	//Whether some triple could match with v inside values. Other variables are left free, so
+	//this never rules out a range that has a solution.
+	fn any_with(&self,store:&TripleStore,v:usize,values:&RangeInclusive<usize>)->bool{
+		let n = store.get_terms().len();
+		let range = |slot:Slot| match slot{
+			Slot::Const(id) => id..=id,
+			Slot::Var(var) if var == v => values.clone(),
+			Slot::Var(_) => 0..=n-1
+		};
+		let (rows,cols) = (range(self.s),range(self.o));
+		match self.p{
+			Slot::Const(p) => store.get_tree(p).any_in_range(rows,cols),
+			Slot::Var(_) => (0..store.get_predicates().len()).any(|p| store.get_tree(p).any_in_range(rows.clone(),cols.clone()))
+		}
+	}
+	//The only variable of the pattern in subject or object position that is already bound
+	fn join_variable(&self,bound:&[bool])->Option<usize>{
+		let shared:Vec<usize> = self.vars().into_iter().filter(|&v| bound[v]).collect();
+		match (shared.as_slice(),self.p){
+			([v],Slot::Var(p)) if *v == p => None,
+			([v],_) => Some(*v),
+			_ => None
+		}
+	}
+}
+
+//Patterns with more constants first, then the ones sharing variables with those already placed
+fn plan(mut patterns:Vec<Pattern>,variables:usize)->Vec<Pattern>{
+	let mut ordered = Vec::with_capacity(patterns.len());
+	let mut bound = vec![false;variables];
+	while !patterns.is_empty(){
+		let connected = |pattern:&Pattern| ordered.is_empty() || pattern.vars().iter().any(|&v| bound[v]);
+		let best = (0..patterns.len())
+			.max_by_key(|&e| (connected(&patterns[e]),patterns[e].constants(),std::cmp::Reverse(e)))
+			.unwrap();
+		let pattern = patterns.remove(best);
+		for v in pattern.vars(){
+			bound[v] = true;
+		}
+		ordered.push(pattern);
+	}
+	ordered
+}
+
+fn chain(store:&TripleStore,rows:Vec<Row>,pattern:&Pattern)->Vec<Row>{
+	rows.iter().flat_map(|row| pattern.solve(store,row).into_iter().filter_map(|triple| pattern.extend(row,triple))).collect()
+}
+
+fn independent(store:&TripleStore,rows:Vec<Row>,pattern:&Pattern,bound:&[bool])->Vec<Row>{
+	let shared:Vec<usize> = pattern.vars().into_iter().filter(|&v| bound[v]).collect();
+	let empty:Row = vec![None;bound.len()];
+	let mut by_key:HashMap<Vec<usize>,Vec<(usize,usize,usize)>> = HashMap::new();
+	for triple in pattern.solve(store,&empty){
+		if let Some(row) = pattern.extend(&empty,triple){
+			let key = shared.iter().map(|&v| row[v].unwrap()).collect();
+			by_key.entry(key).or_default().push(triple);
+		}
+	}
+	rows.iter().flat_map(|row| {
+		let key:Vec<usize> = shared.iter().map(|&v| row[v].unwrap()).collect();
+		by_key.get(&key).into_iter().flatten().filter_map(move |&triple| pattern.extend(row,triple))
+	}).collect()
+}
+
+//Splits values of v in k parts while every side still has something in the part
+fn split_domain(range:RangeInclusive<usize>,k:usize,alive:&dyn Fn(&RangeInclusive<usize>)->bool,found:&mut Vec<usize>){
+	if !alive(&range){
+		return;
+	}
+	if range.start() == range.end(){
+		found.push(*range.start());
+		return;
+	}
+	let width = range.end() - range.start() + 1;
+	let step = width.div_ceil(k);
+	let mut start = *range.start();
+	while start <= *range.end(){
+		let end = std::cmp::min(start+step-1,*range.end());
+		split_domain(start..=end,k,alive,found);
+		start = end+1;
+	}
+}
+
+fn interactive(store:&TripleStore,rows:Vec<Row>,pattern:&Pattern,v:usize)->Vec<Row>{
+	let mut by_value:HashMap<usize,Vec<&Row>> = HashMap::new();
+	for row in &rows{
+		by_value.entry(row[v].unwrap()).or_default().push(row);
+	}
+	let mut values:Vec<usize> = by_value.keys().copied().collect();
+	values.sort_unstable();
+	let alive = |range:&RangeInclusive<usize>| {
+		let first = values.partition_point(|value| value < range.start());
+		first < values.len() && values[first] <= *range.end() && pattern.any_with(store,v,range)
+	};
+	let mut found = Vec::new();
+	split_domain(0..=store.get_terms().len()-1,2,&alive,&mut found);
+	found.into_iter().flat_map(|value| {
+		let group = &by_value[&value];
+		let mut bound = vec![None;group[0].len()];
+		bound[v] = Some(value);
+		let triples = pattern.solve(store,&bound);
+		group.iter().flat_map(move |row| triples.clone().into_iter().filter_map(move |triple| pattern.extend(row,triple)))
+			.collect::<Vec<Row>>()
+	}).collect()
+}
+
+//Both patterns split the domain of their shared variable together, nothing is solved until
+//the variable is bound to a single value
+fn interactive_pair(store:&TripleStore,a:&Pattern,b:&Pattern,v:usize,variables:usize)->Vec<Row>{
+	let alive = |range:&RangeInclusive<usize>| a.any_with(store,v,range) && b.any_with(store,v,range);
+	let mut found = Vec::new();
+	split_domain(0..=store.get_terms().len()-1,2,&alive,&mut found);
+	let mut rows = Vec::new();
+	for value in found{
+		let mut bound = vec![None;variables];
+		bound[v] = Some(value);
+		rows.extend(chain(store,chain(store,vec![bound],a),b));
+	}
+	rows
+}
+
+//Solutions of the conjunction. Every strategy gives the same rows.
+pub fn evaluate(store:&TripleStore,bgp:&Bgp,strategy:JoinStrategy)->Solutions{
+	let mut variables:Vec<String> = Vec::new();
+	let mut predicate:Vec<bool> = Vec::new();
+	let mut resolved = Vec::new();
+	let mut missing = false;
+	for pattern in &bgp.patterns{
+		let mut slot = |term:&PatternTerm,is_predicate:bool| match term{
+			PatternTerm::Var(name) => Slot::Var(match variables.iter().position(|var| var == name){
+				Some(v) => v,
+				None => {
+					variables.push(name.clone());
+					predicate.push(is_predicate);
+					variables.len()-1
+				}
+			}),
+			PatternTerm::Const(term) => {
+				let map = if is_predicate {store.get_predicates()} else {store.get_terms()};
+				match map.index_of(term){
+					Some(id) => Slot::Const(id),
+					None => {
+						missing = true;
+						Slot::Const(usize::MAX)
+					}
+				}
+			}
+		};
+		let s = slot(&pattern.subject,false);
+		let p = slot(&pattern.predicate,true);
+		let o = slot(&pattern.object,false);
+		resolved.push(Pattern{s,p,o});
+	}
+	let mut solutions = Solutions{
+		variables,
+		predicate,
+		rows:Vec::new()
+	};
+	if missing || store.is_empty(){
+		return solutions;
+	}
+	let count = solutions.variables.len();
+	let patterns = plan(resolved,count);
+	let mut bound = vec![false;count];
+	let mut rows:Vec<Row> = vec![vec![None;count]];
+	let mut next = 0;
+	if strategy == JoinStrategy::Interactive && patterns.len() >= 2{
+		let first_vars = patterns[0].vars();
+		let mut first_bound = vec![false;count];
+		for &v in &first_vars{
+			first_bound[v] = true;
+		}
+		if let Some(v) = patterns[1].join_variable(&first_bound){
+			rows = interactive_pair(store,&patterns[0],&patterns[1],v,count);
+			for pattern in &patterns[..2]{
+				for v in pattern.vars(){
+					bound[v] = true;
+				}
+			}
+			next = 2;
+		}
+	}
+	for pattern in &patterns[next..]{
+		if rows.is_empty(){
+			break;
+		}
+		rows = match strategy{
+			JoinStrategy::Chain => chain(store,rows,pattern),
+			JoinStrategy::Independent => independent(store,rows,pattern,&bound),
+			JoinStrategy::Interactive => match pattern.join_variable(&bound){
+				Some(v) => interactive(store,rows,pattern,v),
+				None => chain(store,rows,pattern)
+			}
+		};
+		for v in pattern.vars(){
+			bound[v] = true;
+		}
+	}
+	let mut rows:Vec<Vec<usize>> = rows.into_iter().map(|row| row.into_iter().map(|value| value.unwrap()).collect()).collect();
+	rows.sort_unstable();
+	rows.dedup();
+	solutions.rows = rows;
+	solutions
+}
+
+#[cfg(test)]
+mod tests{
+	use std::cell::Cell;
+	use crate::triples::TripleStore;
+	use super::{Bgp,PatternTerm,JoinStrategy,evaluate,split_domain};
+
+	fn store()->TripleStore{
+		let triples = vec![
+			("<alice>","<knows>","<bob>"),
+			("<alice>","<knows>","<carol>"),
+			("<bob>","<knows>","<carol>"),
+			("<carol>","<knows>","<dave>"),
+			("<bob>","<likes>","<pizza>"),
+			("<carol>","<likes>","<pizza>"),
+			("<dave>","<likes>","<sushi>"),
+			("<alice>","<age>","\"30\""),
+		];
+		TripleStore::from_triples(triples)
+	}
+
+	fn all_strategies(store:&TripleStore,query:&str)->Vec<Vec<String>>{
+		let bgp = Bgp::parse(query).unwrap();
+		let chain = evaluate(store,&bgp,JoinStrategy::Chain);
+		for strategy in [JoinStrategy::Independent,JoinStrategy::Interactive]{
+			assert_eq!(evaluate(store,&bgp,strategy),chain,"{:?} on {}",strategy,query);
+		}
+		chain.to_labels(store).into_iter().map(|row| row.into_iter().map(String::from).collect()).collect()
+	}
+
+	#[test]
+	fn parse_patterns(){
+		let bgp = Bgp::parse("?x <knows> ?y.?y <likes> \"a b\"@en .").unwrap();
+		assert_eq!(bgp.get_patterns().len(),2);
+		assert_eq!(bgp.get_patterns()[0].subject,PatternTerm::Var(String::from("x")));
+		assert_eq!(bgp.get_patterns()[1].object,PatternTerm::Const(String::from("\"a b\"@en")));
+		assert!(Bgp::parse("?x <knows>").is_err());
+		assert!(Bgp::parse("?x ?p ?y . ?p <knows> ?z").is_err());
+		assert!(Bgp::parse("?x <p> ?y ?z <p> ?w").is_err());
+	}
+
+	#[test]
+	fn chain_queries(){
+		let store = store();
+		assert_eq!(all_strategies(&store,"?x <knows> ?y . ?y <likes> <pizza>"),vec![
+			vec!["<alice>","<bob>"],
+			vec!["<alice>","<carol>"],
+			vec!["<bob>","<carol>"]
+		]);
+		assert_eq!(all_strategies(&store,"?x <knows> ?y . ?y <knows> ?z . ?z <likes> ?food"),vec![
+			vec!["<alice>","<bob>","<carol>","<pizza>"],
+			vec!["<alice>","<carol>","<dave>","<sushi>"],
+			vec!["<bob>","<carol>","<dave>","<sushi>"]
+		]);
+	}
+
+	#[test]
+	fn star_and_variable_predicates(){
+		let store = store();
+		assert_eq!(all_strategies(&store,"?x <knows> <carol> . ?x <likes> ?food"),vec![vec!["<bob>","<pizza>"]]);
+		assert_eq!(all_strategies(&store,"<alice> ?p ?o . ?o <likes> ?f"),vec![
+			vec!["<knows>","<bob>","<pizza>"],
+			vec!["<knows>","<carol>","<pizza>"]
+		]);
+		assert_eq!(all_strategies(&store,"?a <likes> ?f . ?b <likes> ?f . ?a <knows> ?b").len(),1);
+		assert!(all_strategies(&store,"?x <hates> ?y").is_empty());
+		assert!(all_strategies(&store,"?x <knows> ?x").is_empty());
+	}
+
+	#[test]
+	fn interactive_prunes(){
+		//Only the ranges holding 5 or 40 are split further
+		let probes = Cell::new(0);
+		let alive = |range:&std::ops::RangeInclusive<usize>| {
+			probes.set(probes.get()+1);
+			range.contains(&5) || range.contains(&40)
+		};
+		let mut found = Vec::new();
+		split_domain(0..=63,2,&alive,&mut found);
+		assert_eq!(found,vec![5,40]);
+		//Whole domain, both halves, then two alive ranges of two children down five levels
+		assert_eq!(probes.get(),1 + 2 + 4*5);
+		//The third pattern joins on ?y, bound to alice and bob. Nobody knows alice, so her
+		//range is pruned before any triple is solved.
+		let store = store();
+		let bgp = Bgp::parse("?z <likes> <pizza> . ?y <knows> ?z . ?x <knows> ?y").unwrap();
+		let solutions = evaluate(&store,&bgp,JoinStrategy::Interactive);
+		assert_eq!(solutions.to_labels(&store),vec![vec!["<carol>","<bob>","<alice>"]]);
+		assert_eq!(solutions,evaluate(&store,&bgp,JoinStrategy::Chain));
+	}
+}
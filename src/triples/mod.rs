@@ -0,0 +1,200 @@
+use std::io::BufRead;
+
+use crate::k2tree::K2tree;
+use crate::webgraph::{IdMap,LineError};
+use crate::webgraph::open_reader;
+
+pub mod ntriples;
+pub mod bgp;
+pub mod rpq;
+
+pub use ntriples::TriplesError;
+pub use bgp::{Bgp,TriplePattern,PatternTerm,JoinStrategy,Solutions,evaluate};
+pub use rpq::{PathExpr,PathQuery};
+
+//RDF graph split by predicate (vertical partitioning): one K2tree<bool> per predicate whose
+//cell (s,o) is set when the triple (s,p,o) exists. Subjects and objects share a dictionary,
+//so the same term has the same id in both positions.
+#[derive(Debug)]
+pub struct TripleStore{
+	terms:IdMap<String>,
+	predicates:IdMap<String>,
+	trees:Vec<K2tree<bool>>,
+	//Distinct triples, counted once while building
+	triples:usize
+}
+
+impl TripleStore{
+	//Terms are kept with their N-Triples syntax (<iri>, _:blank, "literal"@en)
+	pub fn from_triples<S>(triples:impl IntoIterator<Item=(S,S,S)>)->Self where S:Into<String>{
+		let mut terms = IdMap::new();
+		let mut predicates = IdMap::new();
+		let mut pairs:Vec<Vec<(usize,usize)>> = Vec::new();
+		for (subject,predicate,object) in triples{
+			let s = terms.get_or_insert(subject.into());
+			let o = terms.get_or_insert(object.into());
+			let p = predicates.get_or_insert(predicate.into());
+			if p == pairs.len(){
+				pairs.push(Vec::new());
+			}
+			pairs[p].push((s,o));
+		}
+		for pairs in pairs.iter_mut(){
+			pairs.sort_unstable();
+			pairs.dedup();
+		}
+		let triples = pairs.iter().map(Vec::len).sum();
+		let n = terms.len();
+		let trees = pairs.into_iter()
+			.map(|pairs| K2tree::from_triplets(n,n,2,false,pairs.into_iter().map(|(s,o)| (s,o,true))))
+			.collect();
+		TripleStore{
+			terms,
+			predicates,
+			trees,
+			triples
+		}
+	}
+
+	//Loads an N-Triples file (gzip is detected). Stops at the first malformed line.
+	pub fn from_ntriples(filename:&str)->Result<Self,TriplesError>{
+		let mut triples = Vec::new();
+		for (number,line) in open_reader(filename)?.lines().enumerate(){
+			let line = line?;
+			match ntriples::parse_line(&line){
+				Ok(Some((s,p,o))) => triples.push((s.to_string(),p.to_string(),o.to_string())),
+				Ok(None) => continue,
+				Err(message) => return Err(TriplesError::Parse(LineError{line:number+1,message}))
+			}
+		}
+		Ok(Self::from_triples(triples))
+	}
+
+	pub fn get_terms(&self)->&IdMap<String>{
+		&self.terms
+	}
+	pub fn get_predicates(&self)->&IdMap<String>{
+		&self.predicates
+	}
+	pub fn get_tree(&self,predicate:usize)->&K2tree<bool>{
+		&self.trees[predicate]
+	}
+	pub fn len(&self)->usize{
+		self.triples
+	}
+	pub fn is_empty(&self)->bool{
+		self.trees.is_empty()
+	}
+
+	//Triples of one predicate tree matching the bound subject and object
+	fn matches_in(&self,p:usize,s:Option<usize>,o:Option<usize>,output:&mut Vec<(usize,usize,usize)>){
+		let tree = &self.trees[p];
+		match (s,o){
+			(Some(s),Some(o)) => if *tree.get(s,o).unwrap() {output.push((s,p,o))},
+			(Some(s),None) => output.extend(tree.row(s).into_iter().map(|(o,_)| (s,p,o))),
+			(None,Some(o)) => output.extend(tree.column(o).into_iter().map(|(s,_)| (s,p,o))),
+			(None,None) => {
+				let n = self.terms.len();
+				output.extend(tree.range(0..=n-1,0..=n-1).into_iter().map(|((s,o),_)| (s,p,o)));
+			}
+		}
+	}
+
+	//Every triple pattern by ids, None standing for a variable. A bound predicate queries its
+	//own tree (get, row, column or the whole range), an unbound one repeats it on every tree.
+	//Results are sorted by (predicate, subject, object) order of the queried tree.
+	pub fn matches(&self,s:Option<usize>,p:Option<usize>,o:Option<usize>)->Vec<(usize,usize,usize)>{
+		let n = self.terms.len();
+		if s.is_some_and(|s| s >= n) || o.is_some_and(|o| o >= n) || p.is_some_and(|p| p >= self.trees.len()){
+			return Vec::new();
+		}
+		let mut output = Vec::new();
+		match p{
+			Some(p) => self.matches_in(p,s,o,&mut output),
+			None => {
+				for p in 0..self.trees.len(){
+					self.matches_in(p,s,o,&mut output);
+				}
+			}
+		}
+		output
+	}
+
+	//Same as matches with terms written in N-Triples syntax. Unknown terms match nothing.
+	pub fn find(&self,s:Option<&str>,p:Option<&str>,o:Option<&str>)->Vec<(&str,&str,&str)>{
+		let lookup = |term:Option<&str>,map:&IdMap<String>| match term{
+			None => Ok(None),
+			Some(term) => map.index_of(&term.to_string()).map(Some).ok_or(())
+		};
+		let (s,p,o) = match (lookup(s,&self.terms),lookup(p,&self.predicates),lookup(o,&self.terms)){
+			(Ok(s),Ok(p),Ok(o)) => (s,p,o),
+			_ => return Vec::new()
+		};
+		self.matches(s,p,o).into_iter().map(|(s,p,o)| (self.term(s),self.predicate(p),self.term(o))).collect()
+	}
+
+	pub fn term(&self,id:usize)->&str{
+		self.terms.label_of(id).unwrap()
+	}
+	pub fn predicate(&self,id:usize)->&str{
+		self.predicates.label_of(id).unwrap()
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use std::fs::File;
+	use std::io::Write;
+	use super::{TripleStore,TriplesError};
+
+	const DATA:&str = "# people
+<alice> <knows> <bob> .
+<alice> <knows> <carol> .
+<bob> <knows> <carol> .
+<alice> <name> \"Alice\"@en .
+<carol> <likes> <alice> .
+_:b1 <knows> <alice> .
+";
+
+	fn store()->TripleStore{
+		let path = std::env::temp_dir().join(format!("k2tree_{}_people.nt",std::process::id()));
+		File::create(&path).unwrap().write_all(DATA.as_bytes()).unwrap();
+		TripleStore::from_ntriples(path.to_str().unwrap()).unwrap()
+	}
+
+	#[test]
+	fn all_patterns(){
+		let store = store();
+		assert_eq!(store.len(),6);
+		assert_eq!(store.get_predicates().len(),3);
+		let none:Vec<(&str,&str,&str)> = Vec::new();
+		assert_eq!(store.find(Some("<alice>"),Some("<knows>"),Some("<bob>")),vec![("<alice>","<knows>","<bob>")]);
+		assert_eq!(store.find(Some("<bob>"),Some("<knows>"),Some("<alice>")),none);
+		assert_eq!(store.find(Some("<alice>"),Some("<knows>"),None),vec![("<alice>","<knows>","<bob>"),("<alice>","<knows>","<carol>")]);
+		assert_eq!(store.find(None,Some("<knows>"),Some("<carol>")),vec![("<alice>","<knows>","<carol>"),("<bob>","<knows>","<carol>")]);
+		assert_eq!(store.find(None,Some("<likes>"),None),vec![("<carol>","<likes>","<alice>")]);
+		assert_eq!(store.find(Some("<carol>"),None,Some("<alice>")),vec![("<carol>","<likes>","<alice>")]);
+		assert_eq!(store.find(Some("<alice>"),None,None).len(),3);
+		assert_eq!(store.find(None,None,Some("<alice>")),vec![("_:b1","<knows>","<alice>"),("<carol>","<likes>","<alice>")]);
+		assert_eq!(store.find(None,None,None).len(),6);
+		assert_eq!(store.find(Some("<nobody>"),None,None),none);
+		assert_eq!(store.find(None,Some("<name>"),None),vec![("<alice>","<name>","\"Alice\"@en")]);
+	}
+
+	#[test]
+	fn repeated_triples(){
+		let store = TripleStore::from_triples(vec![("<a>","<p>","<b>"),("<a>","<p>","<b>"),("<b>","<q>","<a>")]);
+		assert_eq!(store.len(),2);
+		assert!(!store.is_empty());
+	}
+
+	#[test]
+	fn malformed_line(){
+		let path = std::env::temp_dir().join(format!("k2tree_{}_broken.nt",std::process::id()));
+		File::create(&path).unwrap().write_all(b"<a> <p> <b> .\n<a> <p>\n").unwrap();
+		match TripleStore::from_ntriples(path.to_str().unwrap()){
+			Err(TriplesError::Parse(error)) => assert_eq!(error.line,2),
+			_ => panic!("expected a parse error")
+		}
+	}
+}
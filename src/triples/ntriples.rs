@@ -0,0 +1,109 @@
+use std::fmt;
+use std::io;
+
+use crate::webgraph::LineError;
+
+#[derive(Debug)]
+pub enum TriplesError{
+	Io(io::Error),
+	Parse(LineError)
+}
+
+impl fmt::Display for TriplesError{
+	fn fmt(&self,f:&mut fmt::Formatter<'_>)->fmt::Result{
+		match self{
+			TriplesError::Io(error) => write!(f,"{}",error),
+			TriplesError::Parse(error) => write!(f,"{}",error)
+		}
+	}
+}
+
+impl std::error::Error for TriplesError{}
+
+impl From<io::Error> for TriplesError{
+	fn from(error:io::Error)->Self{
+		TriplesError::Io(error)
+	}
+}
+
+//Next term of the line and the rest of it. Terms keep their N-Triples syntax: <iri>, _:blank
+//or "literal" with its @language or ^^<datatype> suffix.
+pub(crate) fn next_term(line:&str)->Result<(&str,&str),String>{
+	let line = line.trim_start();
+	let end = match line.chars().next(){
+		Some('<') => line.find('>').map(|e| e+1).ok_or_else(|| String::from("unterminated iri"))?,
+		Some('_') if line.starts_with("_:") => line.find(char::is_whitespace).unwrap_or(line.len()),
+		Some('"') => {
+			let mut escaped = false;
+			let mut close = None;
+			for (e,c) in line.char_indices().skip(1){
+				match c{
+					'\\' if !escaped => escaped = true,
+					'"' if !escaped => {
+						close = Some(e);
+						break;
+					},
+					_ => escaped = false
+				}
+			}
+			let close = close.ok_or_else(|| String::from("unterminated literal"))?;
+			let rest = &line[close+1..];
+			let suffix = if rest.starts_with("^^<"){
+				rest.find('>').map(|e| e+1).ok_or_else(|| String::from("unterminated datatype"))?
+			}else if let Some(language) = rest.strip_prefix('@'){
+				1 + language.find(|c:char| !c.is_ascii_alphanumeric() && c != '-').unwrap_or(language.len())
+			}else{
+				0
+			};
+			close + 1 + suffix
+		},
+		Some(_) => return Err(format!("invalid term {}",line.split_whitespace().next().unwrap_or(""))),
+		None => return Err(String::from("missing term"))
+	};
+	Ok(line.split_at(end))
+}
+
+//(subject,predicate,object) of a line, None for empty and comment lines
+pub(crate) fn parse_line(line:&str)->Result<Option<(&str,&str,&str)>,String>{
+	let trimmed = line.trim();
+	if trimmed.is_empty() || trimmed.starts_with('#'){
+		return Ok(None);
+	}
+	let (subject,rest) = next_term(trimmed)?;
+	if subject.starts_with('"'){
+		return Err(String::from("literal as subject"));
+	}
+	let (predicate,rest) = next_term(rest)?;
+	if !predicate.starts_with('<'){
+		return Err(String::from("predicate must be an iri"));
+	}
+	let (object,rest) = next_term(rest)?;
+	//Only a comment may follow the closing dot
+	let after = rest.trim().strip_prefix('.').map(str::trim_start);
+	if !after.is_some_and(|after| after.is_empty() || after.starts_with('#')){
+		return Err(String::from("expected . at the end of the triple"));
+	}
+	Ok(Some((subject,predicate,object)))
+}
+
+#[cfg(test)]
+mod tests{
+	use super::parse_line;
+
+	#[test]
+	fn terms(){
+		assert_eq!(parse_line("<http://a> <http://p> <http://b> .").unwrap(),Some(("<http://a>","<http://p>","<http://b>")));
+		assert_eq!(parse_line("_:x <p> \"say \\\"hi\\\"\"@en .").unwrap(),Some(("_:x","<p>","\"say \\\"hi\\\"\"@en")));
+		assert_eq!(parse_line("<a> <p> \"5\"^^<http://www.w3.org/2001/XMLSchema#integer>.").unwrap(),
+			Some(("<a>","<p>","\"5\"^^<http://www.w3.org/2001/XMLSchema#integer>")));
+		assert_eq!(parse_line("  # comment").unwrap(),None);
+		assert_eq!(parse_line("<a> <p> <b> . # trailing comment").unwrap(),Some(("<a>","<p>","<b>")));
+		assert_eq!(parse_line("<a> <p> \"x\".#note").unwrap(),Some(("<a>","<p>","\"x\"")));
+		assert!(parse_line("<a> <p> <b> . <c>").is_err());
+		assert!(parse_line("<a> <p> <b> # no dot").is_err());
+		assert!(parse_line("<a> <p> <b>").is_err());
+		assert!(parse_line("\"x\" <p> <b> .").is_err());
+		assert!(parse_line("<a> _:p <b> .").is_err());
+		assert!(parse_line("<a> <p> \"open .").is_err());
+	}
+}
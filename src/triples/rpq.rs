@@ -0,0 +1,387 @@
+use std::collections::{HashSet,VecDeque};
+
+use super::TripleStore;
+use super::ntriples::next_term;
+
+//Regular path expression over predicates. Written as "knows+/worksAt", "^knows|(likes/knows)?",
+//with / for sequence, | for alternative, postfix *, + and ?, and ^ for the inverse direction.
+//Labels are N-Triples iris, a bare name stands for the iri <name>.
+#[derive(Debug,Clone,PartialEq)]
+pub enum PathExpr{
+	Label(String),
+	Inverse(Box<PathExpr>),
+	Sequence(Box<PathExpr>,Box<PathExpr>),
+	Alternative(Box<PathExpr>,Box<PathExpr>),
+	Star(Box<PathExpr>),
+	Plus(Box<PathExpr>),
+	Optional(Box<PathExpr>)
+}
+
+impl PathExpr{
+	pub fn parse(input:&str)->Result<Self,String>{
+		let (expr,rest) = Self::alternative(input)?;
+		if !rest.trim().is_empty(){
+			return Err(format!("unexpected {}",rest.trim()));
+		}
+		Ok(expr)
+	}
+
+	fn alternative(input:&str)->Result<(Self,&str),String>{
+		let (mut expr,mut rest) = Self::sequence(input)?;
+		while let Some(tail) = rest.trim_start().strip_prefix('|'){
+			let (right,tail) = Self::sequence(tail)?;
+			expr = PathExpr::Alternative(Box::new(expr),Box::new(right));
+			rest = tail;
+		}
+		Ok((expr,rest))
+	}
+
+	fn sequence(input:&str)->Result<(Self,&str),String>{
+		let (mut expr,mut rest) = Self::unary(input)?;
+		while let Some(tail) = rest.trim_start().strip_prefix('/'){
+			let (right,tail) = Self::unary(tail)?;
+			expr = PathExpr::Sequence(Box::new(expr),Box::new(right));
+			rest = tail;
+		}
+		Ok((expr,rest))
+	}
+
+	fn unary(input:&str)->Result<(Self,&str),String>{
+		let (mut expr,mut rest) = Self::primary(input)?;
+		loop{
+			let tail = rest.trim_start();
+			expr = match tail.chars().next(){
+				Some('*') => PathExpr::Star(Box::new(expr)),
+				Some('+') => PathExpr::Plus(Box::new(expr)),
+				Some('?') => PathExpr::Optional(Box::new(expr)),
+				_ => return Ok((expr,rest))
+			};
+			rest = &tail[1..];
+		}
+	}
+
+	fn primary(input:&str)->Result<(Self,&str),String>{
+		let input = input.trim_start();
+		if let Some(rest) = input.strip_prefix('^'){
+			let (expr,rest) = Self::primary(rest)?;
+			return Ok((PathExpr::Inverse(Box::new(expr)),rest));
+		}
+		if let Some(rest) = input.strip_prefix('('){
+			let (expr,rest) = Self::alternative(rest)?;
+			return match rest.trim_start().strip_prefix(')'){
+				Some(rest) => Ok((expr,rest)),
+				None => Err(String::from("expected )"))
+			};
+		}
+		if input.starts_with('<'){
+			let (label,rest) = next_term(input)?;
+			return Ok((PathExpr::Label(label.to_string()),rest));
+		}
+		let end = input.find(|c:char| !c.is_alphanumeric() && c != '_' && c != '-' && c != ':').unwrap_or(input.len());
+		if end == 0{
+			return Err(match input.split_whitespace().next(){
+				Some(token) => format!("expected a label before {}",token),
+				None => String::from("missing label")
+			});
+		}
+		Ok((PathExpr::Label(format!("<{}>",&input[..end])),&input[end..]))
+	}
+}
+
+//Move along an arc of a predicate: inverse ones go from object to subject (column queries),
+//the rest from subject to object (row queries). Unknown predicates never match.
+#[derive(Debug,Clone,Copy)]
+struct Step{
+	predicate:Option<usize>,
+	inverse:bool
+}
+
+//NFA with its epsilon transitions already closed: closures[q] holds every state reachable
+//from q through epsilon moves, q included.
+#[derive(Debug)]
+struct Automaton{
+	steps:Vec<Vec<(Step,usize)>>,
+	closures:Vec<Vec<usize>>,
+	start:usize,
+	accept:usize
+}
+
+impl Automaton{
+	fn new(steps:Vec<Vec<(Step,usize)>>,epsilon:&[Vec<usize>],start:usize,accept:usize)->Self{
+		let closures = (0..steps.len()).map(|q| {
+			let mut seen = vec![false;steps.len()];
+			seen[q] = true;
+			let mut pending = vec![q];
+			let mut closure = Vec::new();
+			while let Some(state) = pending.pop(){
+				closure.push(state);
+				for &next in &epsilon[state]{
+					if !seen[next]{
+						seen[next] = true;
+						pending.push(next);
+					}
+				}
+			}
+			closure
+		}).collect();
+		Automaton{steps,closures,start,accept}
+	}
+
+	//Same language read backwards: arcs turned around, walked in the opposite direction
+	fn reversed(&self,epsilon:&[Vec<usize>])->Self{
+		let mut steps = vec![Vec::new();self.steps.len()];
+		let mut reversed_epsilon = vec![Vec::new();epsilon.len()];
+		for (q,transitions) in self.steps.iter().enumerate(){
+			for &(step,next) in transitions{
+				steps[next].push((Step{predicate:step.predicate,inverse:!step.inverse},q));
+			}
+		}
+		for (q,targets) in epsilon.iter().enumerate(){
+			for &next in targets{
+				reversed_epsilon[next].push(q);
+			}
+		}
+		Automaton::new(steps,&reversed_epsilon,self.accept,self.start)
+	}
+}
+
+//Thompson construction between the states from and to
+struct Compiler<'a>{
+	store:&'a TripleStore,
+	steps:Vec<Vec<(Step,usize)>>,
+	epsilon:Vec<Vec<usize>>
+}
+
+impl Compiler<'_>{
+	fn state(&mut self)->usize{
+		self.steps.push(Vec::new());
+		self.epsilon.push(Vec::new());
+		self.steps.len()-1
+	}
+
+	fn compile(&mut self,expr:&PathExpr,inverse:bool,from:usize,to:usize){
+		match expr{
+			PathExpr::Label(label) => {
+				let predicate = self.store.get_predicates().index_of(label);
+				self.steps[from].push((Step{predicate,inverse},to));
+			},
+			PathExpr::Inverse(inner) => self.compile(inner,!inverse,from,to),
+			PathExpr::Sequence(first,second) => {
+				let middle = self.state();
+				//The inverse of a/b is ^b/^a
+				let (first,second) = if inverse {(second,first)} else {(first,second)};
+				self.compile(first,inverse,from,middle);
+				self.compile(second,inverse,middle,to);
+			},
+			PathExpr::Alternative(left,right) => {
+				self.compile(left,inverse,from,to);
+				self.compile(right,inverse,from,to);
+			},
+			PathExpr::Star(inner) => {
+				let middle = self.state();
+				self.epsilon[from].push(middle);
+				self.epsilon[middle].push(to);
+				self.compile(inner,inverse,middle,middle);
+			},
+			PathExpr::Plus(inner) => {
+				let (begin,end) = (self.state(),self.state());
+				self.epsilon[from].push(begin);
+				self.compile(inner,inverse,begin,end);
+				self.epsilon[end].push(begin);
+				self.epsilon[end].push(to);
+			},
+			PathExpr::Optional(inner) => {
+				self.compile(inner,inverse,from,to);
+				self.epsilon[from].push(to);
+			}
+		}
+	}
+}
+
+//Regular path query compiled against a store. Answers are found with a breadth first search
+//over (node, automaton state) pairs, every step being a row or column query of one predicate.
+#[derive(Debug)]
+pub struct PathQuery<'a>{
+	store:&'a TripleStore,
+	forward:Automaton,
+	backward:Automaton,
+	limit:Option<usize>
+}
+
+impl<'a> PathQuery<'a>{
+	pub fn new(store:&'a TripleStore,expr:&PathExpr)->Self{
+		let mut compiler = Compiler{store,steps:Vec::new(),epsilon:Vec::new()};
+		let start = compiler.state();
+		let accept = compiler.state();
+		compiler.compile(expr,false,start,accept);
+		let forward = Automaton::new(compiler.steps,&compiler.epsilon,start,accept);
+		let backward = forward.reversed(&compiler.epsilon);
+		PathQuery{store,forward,backward,limit:None}
+	}
+
+	//Stops every query after this many results
+	pub fn limit(mut self,limit:usize)->Self{
+		self.limit = Some(limit);
+		self
+	}
+
+	//Calls report with every node reaching an accepting state, once each and nearest first,
+	//until it returns false
+	fn explore<F>(&self,automaton:&Automaton,node:usize,mut report:F) where F:FnMut(usize)->bool{
+		let mut visited = HashSet::new();
+		let mut reported = HashSet::new();
+		let mut queue = VecDeque::new();
+		for &state in &automaton.closures[automaton.start]{
+			visited.insert((node,state));
+			queue.push_back((node,state));
+		}
+		while let Some((node,state)) = queue.pop_front(){
+			if state == automaton.accept && reported.insert(node) && !report(node){
+				return;
+			}
+			for &(step,next) in &automaton.steps[state]{
+				let predicate = match step.predicate{
+					Some(predicate) => predicate,
+					None => continue
+				};
+				let tree = self.store.get_tree(predicate);
+				let neighbours:Vec<usize> = if step.inverse{
+					tree.column(node).into_iter().map(|(i,_)| i).collect()
+				}else{
+					tree.row(node).into_iter().map(|(j,_)| j).collect()
+				};
+				for neighbour in neighbours{
+					for &closed in &automaton.closures[next]{
+						if visited.insert((neighbour,closed)){
+							queue.push_back((neighbour,closed));
+						}
+					}
+				}
+			}
+		}
+	}
+
+	fn collect(&self,automaton:&Automaton,node:usize)->Vec<usize>{
+		assert!(node<self.store.get_terms().len(),"term overflows store");
+		let limit = self.limit.unwrap_or(usize::MAX);
+		let mut output = Vec::new();
+		if limit > 0{
+			self.explore(automaton,node,|found| {
+				output.push(found);
+				output.len() < limit
+			});
+		}
+		output
+	}
+
+	//Nodes at the end of a matching path starting at source, nearest first
+	pub fn targets_of(&self,source:usize)->Vec<usize>{
+		self.collect(&self.forward,source)
+	}
+
+	//Nodes at the start of a matching path ending at target, nearest first
+	pub fn sources_of(&self,target:usize)->Vec<usize>{
+		self.collect(&self.backward,target)
+	}
+
+	pub fn holds(&self,source:usize,target:usize)->bool{
+		assert!(source<self.store.get_terms().len() && target<self.store.get_terms().len(),"term overflows store");
+		let mut found = false;
+		self.explore(&self.forward,source,|node| {
+			found = node == target;
+			!found
+		});
+		found
+	}
+
+	//Every (source, target) pair, by source and then nearest target first
+	pub fn pairs(&self)->Vec<(usize,usize)>{
+		let limit = self.limit.unwrap_or(usize::MAX);
+		let mut output = Vec::new();
+		for source in 0..self.store.get_terms().len(){
+			if output.len() >= limit{
+				break;
+			}
+			self.explore(&self.forward,source,|target| {
+				output.push((source,target));
+				output.len() < limit
+			});
+		}
+		output
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use super::{PathExpr,PathQuery};
+	use crate::triples::TripleStore;
+
+	fn store()->TripleStore{
+		TripleStore::from_triples(vec![
+			("<alice>","<knows>","<bob>"),
+			("<bob>","<knows>","<carol>"),
+			("<dave>","<knows>","<alice>"),
+			("<bob>","<worksAt>","<init>"),
+			("<carol>","<worksAt>","<acme>"),
+			("<carol>","<knows>","<bob>")
+		])
+	}
+
+	fn ids(store:&TripleStore,labels:&[&str])->Vec<usize>{
+		labels.iter().map(|label| store.get_terms().index_of(&label.to_string()).unwrap()).collect()
+	}
+
+	#[test]
+	fn parse(){
+		let knows = || Box::new(PathExpr::Label(String::from("<knows>")));
+		assert_eq!(PathExpr::parse("knows+/<worksAt>").unwrap(),PathExpr::Sequence(
+			Box::new(PathExpr::Plus(knows())),
+			Box::new(PathExpr::Label(String::from("<worksAt>")))
+		));
+		assert_eq!(PathExpr::parse("^knows | (knows)*").unwrap(),PathExpr::Alternative(
+			Box::new(PathExpr::Inverse(knows())),
+			Box::new(PathExpr::Star(knows()))
+		));
+		assert!(PathExpr::parse("knows/").is_err());
+		assert!(PathExpr::parse("(knows").is_err());
+		assert!(PathExpr::parse("knows likes").is_err());
+	}
+
+	#[test]
+	fn paths(){
+		let store = store();
+		let query = PathQuery::new(&store,&PathExpr::parse("knows+/worksAt").unwrap());
+		let alice = ids(&store,&["<alice>"])[0];
+		assert_eq!(query.targets_of(alice),ids(&store,&["<init>","<acme>"]));
+		let mut sources = query.sources_of(ids(&store,&["<acme>"])[0]);
+		sources.sort();
+		let mut expected = ids(&store,&["<alice>","<bob>","<carol>","<dave>"]);
+		expected.sort();
+		assert_eq!(sources,expected);
+		assert!(query.holds(alice,ids(&store,&["<acme>"])[0]));
+		assert!(!query.holds(ids(&store,&["<acme>"])[0],alice));
+		//Every pair agrees with the searches from each end
+		for (source,target) in query.pairs(){
+			assert!(query.sources_of(target).contains(&source));
+		}
+		assert_eq!(query.pairs().len(),8);
+	}
+
+	#[test]
+	fn inverse_star_and_limit(){
+		let store = store();
+		let alice = ids(&store,&["<alice>"])[0];
+		let colleagues = PathQuery::new(&store,&PathExpr::parse("^worksAt/knows?").unwrap());
+		assert_eq!(colleagues.targets_of(ids(&store,&["<init>"])[0]),ids(&store,&["<bob>","<carol>"]));
+		let inverse = PathQuery::new(&store,&PathExpr::parse("^(knows/worksAt)").unwrap());
+		assert_eq!(inverse.targets_of(ids(&store,&["<acme>"])[0]),ids(&store,&["<bob>"]));
+		let star = PathQuery::new(&store,&PathExpr::parse("knows*").unwrap());
+		assert_eq!(star.targets_of(alice),ids(&store,&["<alice>","<bob>","<carol>"]));
+		assert_eq!(star.pairs().len(),3 + 2 + 2 + 4 + 1 + 1);
+		let limited = PathQuery::new(&store,&PathExpr::parse("knows*").unwrap()).limit(2);
+		assert_eq!(limited.targets_of(alice),ids(&store,&["<alice>","<bob>"]));
+		assert_eq!(limited.pairs().len(),2);
+		let unknown = PathQuery::new(&store,&PathExpr::parse("knows/likes").unwrap());
+		assert!(unknown.pairs().is_empty());
+	}
+}
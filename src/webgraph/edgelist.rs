@@ -0,0 +1,361 @@
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+use super::open_reader;
+use super::options::ReadOptions;
+use crate::k2tree::K2tree;
+use crate::reorder::{Permutation,Reordering,ReorderedK2tree};
+use crate::sparse::Coo;
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Delimiter{
+	//Picked from the first data line: tab, then comma, then runs of spaces
+	Auto,
+	Tab,
+	Comma,
+	Whitespace,
+	Char(char)
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct LineError{
+	pub line:usize,
+	pub message:String
+}
+
+#[derive(Debug)]
+pub enum EdgeListError{
+	Io(io::Error),
+	//Every malformed line of the file
+	Parse(Vec<LineError>),
+	//No edges and no node count, or a node count of 0
+	Empty
+}
+
+impl fmt::Display for LineError{
+	fn fmt(&self,f:&mut fmt::Formatter<'_>)->fmt::Result{
+		write!(f,"line {}: {}",self.line,self.message)
+	}
+}
+
+impl fmt::Display for EdgeListError{
+	fn fmt(&self,f:&mut fmt::Formatter<'_>)->fmt::Result{
+		match self{
+			EdgeListError::Io(error) => write!(f,"{}",error),
+			EdgeListError::Parse(errors) => {
+				write!(f,"{} malformed lines",errors.len())?;
+				for error in errors{
+					write!(f,"\n{}",error)?;
+				}
+				Ok(())
+			},
+			EdgeListError::Empty => write!(f,"edge list has no nodes")
+		}
+	}
+}
+
+impl std::error::Error for EdgeListError{}
+
+impl From<io::Error> for EdgeListError{
+	fn from(error:io::Error)->Self{
+		EdgeListError::Io(error)
+	}
+}
+
+//Reader for "from to [weight]" files. Node ids are dense indices, the node count is
+//max id + 1 unless given. Gzip input is detected from its magic bytes.
+#[derive(Debug,Clone)]
+pub struct EdgeListReader{
+	delimiter:Delimiter,
+	comment_prefixes:Vec<String>,
+	skip_header:usize,
+	nodes:Option<usize>,
+	options:ReadOptions
+}
+
+impl Default for EdgeListReader{
+	fn default()->Self{
+		EdgeListReader{
+			delimiter:Delimiter::Auto,
+			comment_prefixes:vec![String::from("#"),String::from("%")],
+			skip_header:0,
+			nodes:None,
+			options:ReadOptions::default()
+		}
+	}
+}
+
+impl EdgeListReader{
+	pub fn new()->Self{
+		Self::default()
+	}
+	pub fn delimiter(mut self,delimiter:Delimiter)->Self{
+		self.delimiter = delimiter;
+		self
+	}
+	pub fn comment_prefixes(mut self,prefixes:&[&str])->Self{
+		self.comment_prefixes = prefixes.iter().map(|prefix| prefix.to_string()).collect();
+		self
+	}
+	//Number of lines skipped at the start of the file, before looking for comments
+	pub fn skip_header(mut self,lines:usize)->Self{
+		self.skip_header = lines;
+		self
+	}
+	pub fn nodes(mut self,nodes:usize)->Self{
+		self.nodes = Some(nodes);
+		self
+	}
+	pub fn k(mut self,k:usize)->Self{
+		self.options = self.options.k(k);
+		self
+	}
+	//Read every edge as undirected and build a symmetric tree (upper triangle only)
+	pub fn symmetrize(mut self,symmetrize:bool)->Self{
+		self.options = self.options.symmetrize(symmetrize);
+		self
+	}
+	pub fn drop_self_loops(mut self,drop_self_loops:bool)->Self{
+		self.options = self.options.drop_self_loops(drop_self_loops);
+		self
+	}
+	pub fn options(mut self,options:ReadOptions)->Self{
+		self.options = options;
+		self
+	}
+
+	fn build<W>(&self,coo:Coo<W>)->K2tree<W> where W:Eq + Clone{
+		let (rows,cols) = (coo.get_rows(),coo.get_cols());
+		let background = coo.get_background().clone();
+		self.options.build(rows,cols,background,false,coo.into_triplets())
+	}
+
+	//Extra columns are ignored
+	pub fn read(&self,filename:&str)->Result<K2tree<bool>,EdgeListError>{
+		let coo = self.read_coo(filename,false,|_| Ok(true))?;
+		Ok(self.build(coo))
+	}
+
+	//Like read, with the nodes renumbered by the strategy before building. Queries on the
+	//result keep taking the ids of the file.
+	pub fn read_reordered(&self,filename:&str,reordering:&Reordering)->Result<ReorderedK2tree<bool>,EdgeListError>{
+		let coo = self.read_coo(filename,false,|_| Ok(true))?;
+		let nodes = coo.get_rows();
+		let edges:Vec<(usize,usize)> = coo.into_triplets().map(|(i,j,_)| (i,j)).collect();
+		let permutation = Permutation::compute(nodes,&edges,reordering);
+		let renumbered = edges.into_iter().map(|(i,j)| (permutation.apply(i),permutation.apply(j),true));
+		let tree = self.options.build(nodes,nodes,false,false,renumbered);
+		Ok(ReorderedK2tree::new(tree,permutation))
+	}
+
+	//The optional third column is the value of the cell, lines without it take missing
+	pub fn read_weighted<W>(&self,filename:&str,background:W,missing:W)->Result<K2tree<W>,EdgeListError>
+	where W:FromStr + Eq + Clone{
+		let coo = self.read_coo(filename,background,|field| match field{
+			Some(field) => field.parse::<W>().map_err(|_| format!("invalid weight {}",field)),
+			None => Ok(missing.clone())
+		})?;
+		Ok(self.build(coo))
+	}
+
+	pub fn read_coo<W,F>(&self,filename:&str,background:W,weight:F)->Result<Coo<W>,EdgeListError>
+	where F:Fn(Option<&str>)->Result<W,String>{
+		let mut edges = Vec::new();
+		let mut errors = Vec::new();
+		let mut delimiter = self.delimiter;
+		for (number,line) in open_reader(filename)?.lines().enumerate().skip(self.skip_header){
+			let line = line?;
+			let line = line.trim();
+			if line.is_empty() || self.comment_prefixes.iter().any(|prefix| line.starts_with(prefix.as_str())){
+				continue;
+			}
+			if delimiter == Delimiter::Auto{
+				delimiter = detect(line);
+			}
+			match parse_line(line,delimiter,&weight){
+				Ok((from,to,_)) if !self.options.keeps(from,to) => continue,
+				//A fixed node count is checked edge by edge, so the error points at its line
+				Ok((from,to,_)) if self.nodes.is_some_and(|nodes| std::cmp::max(from,to) >= nodes) => {
+					let message = format!("node id {} overflows {} nodes",std::cmp::max(from,to),self.nodes.unwrap());
+					errors.push(LineError{line:number+1,message});
+				},
+				Ok(edge) => edges.push(edge),
+				Err(message) => errors.push(LineError{line:number+1,message})
+			}
+		}
+		if !errors.is_empty(){
+			return Err(EdgeListError::Parse(errors));
+		}
+		let nodes = self.nodes.unwrap_or_else(|| edges.iter().map(|(from,to,_)| std::cmp::max(*from,*to)+1).max().unwrap_or(0));
+		//A fixed node count allows a graph without edges, but not one without nodes
+		if nodes == 0{
+			return Err(EdgeListError::Empty);
+		}
+		Ok(Coo::from_triplets(nodes,nodes,background,edges))
+	}
+}
+
+fn detect(line:&str)->Delimiter{
+	if line.contains('\t'){
+		Delimiter::Tab
+	}else if line.contains(','){
+		Delimiter::Comma
+	}else{
+		Delimiter::Whitespace
+	}
+}
+
+fn split(line:&str,delimiter:Delimiter)->Vec<&str>{
+	match delimiter{
+		Delimiter::Tab => line.split('\t').map(str::trim).collect(),
+		Delimiter::Comma => line.split(',').map(str::trim).collect(),
+		Delimiter::Char(c) => line.split(c).map(str::trim).collect(),
+		Delimiter::Whitespace | Delimiter::Auto => line.split_whitespace().collect()
+	}
+}
+
+fn parse_line<W,F>(line:&str,delimiter:Delimiter,weight:&F)->Result<(usize,usize,W),String>
+where F:Fn(Option<&str>)->Result<W,String>{
+	let fields = split(line,delimiter);
+	if fields.len() < 2{
+		return Err(String::from("expected at least two columns"));
+	}
+	let from = fields[0].parse::<usize>().map_err(|_| format!("invalid node id {}",fields[0]))?;
+	let to = fields[1].parse::<usize>().map_err(|_| format!("invalid node id {}",fields[1]))?;
+	let value = weight(fields.get(2).copied())?;
+	Ok((from,to,value))
+}
+
+#[cfg(test)]
+mod tests{
+	use std::fs::File;
+	use std::io::Write;
+	use flate2::Compression;
+	use flate2::write::GzEncoder;
+	use crate::reorder::Reordering;
+	use super::{EdgeListReader,EdgeListError,Delimiter};
+
+	fn temp_path(name:&str)->String{
+		let path = std::env::temp_dir().join(format!("k2tree_{}_{}",std::process::id(),name));
+		path.to_str().unwrap().to_string()
+	}
+
+	fn write_temp(name:&str,content:&str)->String{
+		let path = temp_path(name);
+		File::create(&path).unwrap().write_all(content.as_bytes()).unwrap();
+		path
+	}
+
+	#[test]
+	fn auto_delimiters(){
+		for (name,content) in [("tab.txt","0\t1\n2\t3\n"),("space.txt","0  1\n2 3\n"),("comma.csv","0, 1\n2,3\n")]{
+			let path = write_temp(name,content);
+			let k2tree = EdgeListReader::new().read(&path).unwrap();
+			assert_eq!(k2tree.get_rows(),4);
+			assert!(*k2tree.get(0,1).unwrap());
+			assert!(*k2tree.get(2,3).unwrap());
+			assert_eq!(k2tree.range(0..=3,0..=3).len(),2);
+		}
+	}
+
+	#[test]
+	fn weights_header_and_comments(){
+		let path = write_temp("weighted.csv","source;target;weight\n// generated\n0;2;7\n1;1;-3\n");
+		let k2tree = EdgeListReader::new()
+			.delimiter(Delimiter::Char(';'))
+			.comment_prefixes(&["//"])
+			.skip_header(1)
+			.nodes(5)
+			.read_weighted(&path,0i64,1)
+			.unwrap();
+		assert_eq!(k2tree.get_rows(),5);
+		assert_eq!(k2tree.row(0),vec![(2,&7)]);
+		assert_eq!(*k2tree.get(1,1).unwrap(),-3);
+	}
+
+	#[test]
+	fn gzip_input(){
+		let path = temp_path("edges.txt.gz");
+		let mut encoder = GzEncoder::new(File::create(&path).unwrap(),Compression::default());
+		encoder.write_all(b"# comment\n0 3\n3 0\n").unwrap();
+		encoder.finish().unwrap();
+		let k2tree = EdgeListReader::new().read(&path).unwrap();
+		assert!(*k2tree.get(3,0).unwrap());
+		assert!(!*k2tree.get(1,1).unwrap());
+	}
+
+	#[test]
+	fn symmetrize_and_drop_self_loops(){
+		let path = write_temp("undirected.txt","0 1
+2 2
+3 1
+");
+		let k2tree = EdgeListReader::new().symmetrize(true).drop_self_loops(true).read(&path).unwrap();
+		assert!(k2tree.is_symmetric());
+		assert!(*k2tree.get(1,0).unwrap());
+		assert!(*k2tree.get(1,3).unwrap());
+		assert!(!*k2tree.get(2,2).unwrap());
+		assert_eq!(k2tree.row(1),vec![(0,&true),(3,&true)]);
+		assert_eq!(k2tree.range(0..=3,0..=3).len(),4);
+		let directed = EdgeListReader::new().read(&path).unwrap();
+		assert!(!*directed.get(1,0).unwrap());
+		assert!(*directed.get(2,2).unwrap());
+	}
+
+	#[test]
+	fn reordered_read(){
+		let path = write_temp("reorder.txt","0 2\n2 4\n4 0\n1 3\n5 5\n");
+		let reordered = EdgeListReader::new().nodes(6).symmetrize(true).drop_self_loops(true).read_reordered(&path,&Reordering::Bfs).unwrap();
+		assert!(reordered.get_tree().is_symmetric());
+		assert_eq!(reordered.get_permutation().len(),6);
+		assert_eq!(reordered.row(4).into_iter().map(|(j,_)| j).collect::<Vec<_>>(),vec![0,2]);
+		assert!(*reordered.get(3,1).unwrap());
+		assert!(!*reordered.get(5,5).unwrap());
+		//The triangle 0-2-4 comes first
+		assert_eq!((0..3).map(|e| reordered.get_permutation().inverse(e)).collect::<Vec<_>>(),vec![0,2,4]);
+	}
+
+	#[test]
+	fn per_line_errors(){
+		let path = write_temp("broken.txt","0\t1\n1\n2\tx\n3\t0\n");
+		match EdgeListReader::new().read(&path){
+			Err(EdgeListError::Parse(errors)) => {
+				assert_eq!(errors.iter().map(|error| error.line).collect::<Vec<_>>(),vec![2,3]);
+				assert_eq!(errors[1].message,"invalid node id x");
+			},
+			_ => panic!("expected parse errors")
+		}
+		let path = write_temp("overflow.txt","# two nodes\n0 1\n1 2\n0 0\n5 1\n");
+		match EdgeListReader::new().nodes(2).read(&path){
+			Err(EdgeListError::Parse(errors)) => {
+				assert_eq!(errors.iter().map(|error| error.line).collect::<Vec<_>>(),vec![3,5]);
+				assert_eq!(errors[1].message,"node id 5 overflows 2 nodes");
+			},
+			_ => panic!("expected overflow errors")
+		}
+		let path = write_temp("bad_weight.txt","0 1 5\n1 0 x\n");
+		assert!(matches!(EdgeListReader::new().read_weighted(&path,0u32,1),Err(EdgeListError::Parse(_))));
+	}
+
+	#[test]
+	fn optional_weights(){
+		let path = write_temp("mixed.txt","0 1 5\n1 2\n2 0 -4\n3 3\n");
+		let k2tree = EdgeListReader::new().read_weighted(&path,0i32,1).unwrap();
+		assert_eq!(*k2tree.get(0,1).unwrap(),5);
+		assert_eq!(*k2tree.get(1,2).unwrap(),1);
+		assert_eq!(*k2tree.get(2,0).unwrap(),-4);
+		assert_eq!(*k2tree.get(3,3).unwrap(),1);
+		assert_eq!(k2tree.range(0..=3,0..=3).len(),4);
+	}
+
+	#[test]
+	fn no_nodes(){
+		let path = write_temp("only_comments.txt","# nothing\n");
+		assert!(matches!(EdgeListReader::new().read(&path),Err(EdgeListError::Empty)));
+		assert!(matches!(EdgeListReader::new().nodes(0).read(&path),Err(EdgeListError::Empty)));
+		let path = write_temp("self_loop.txt","0 0\n");
+		assert!(matches!(EdgeListReader::new().nodes(0).drop_self_loops(true).read(&path),Err(EdgeListError::Empty)));
+		assert_eq!(EdgeListReader::new().nodes(3).read(&path).unwrap().get_rows(),3);
+	}
+}
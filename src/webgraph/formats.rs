@@ -0,0 +1,342 @@
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+use super::open_reader;
+use super::options::ReadOptions;
+use super::edgelist::LineError;
+use crate::k2tree::K2tree;
+
+#[derive(Debug)]
+pub enum GraphFormatError{
+	Io(io::Error),
+	Header(String),
+	Parse(LineError)
+}
+
+impl fmt::Display for GraphFormatError{
+	fn fmt(&self,f:&mut fmt::Formatter<'_>)->fmt::Result{
+		match self{
+			GraphFormatError::Io(error) => write!(f,"{}",error),
+			GraphFormatError::Header(message) => write!(f,"invalid header: {}",message),
+			GraphFormatError::Parse(error) => write!(f,"{}",error)
+		}
+	}
+}
+
+impl std::error::Error for GraphFormatError{}
+
+impl From<io::Error> for GraphFormatError{
+	fn from(error:io::Error)->Self{
+		GraphFormatError::Io(error)
+	}
+}
+
+fn parse_error(line:usize,message:String)->GraphFormatError{
+	GraphFormatError::Parse(LineError{line,message})
+}
+
+//1-based node id of a file into a 0-based index
+fn parse_node(token:&str,nodes:usize,line:usize)->Result<usize,GraphFormatError>{
+	match token.parse::<usize>(){
+		Ok(node) if node >= 1 && node <= nodes => Ok(node-1),
+		_ => Err(parse_error(line,format!("invalid node id {}",token)))
+	}
+}
+
+struct MetisHeader{
+	nodes:usize,
+	edges:usize,
+	vertex_sizes:bool,
+	vertex_weights:usize,
+	edge_weights:bool
+}
+
+fn parse_metis_header(line:&str)->Result<MetisHeader,GraphFormatError>{
+	let tokens:Vec<&str> = line.split_whitespace().collect();
+	if tokens.len() < 2 || tokens.len() > 4{
+		return Err(GraphFormatError::Header(String::from(line)));
+	}
+	let numbers:Vec<usize> = tokens.iter().map(|token| token.parse::<usize>()).collect::<Result<_,_>>()
+		.map_err(|_| GraphFormatError::Header(String::from(line)))?;
+	//fmt is up to three binary digits: vertex sizes, vertex weights, edge weights
+	let format = tokens.get(2).copied().unwrap_or("0");
+	if format.len() > 3 || format.chars().any(|c| c != '0' && c != '1'){
+		return Err(GraphFormatError::Header(format!("invalid fmt {}",format)));
+	}
+	let format = format!("{:0>3}",format);
+	let flags:Vec<bool> = format.chars().map(|c| c == '1').collect();
+	//The tree needs at least one node
+	if numbers[0] == 0{
+		return Err(GraphFormatError::Header(String::from("graph has no nodes")));
+	}
+	let constraints = numbers.get(3).copied().unwrap_or(1);
+	Ok(MetisHeader{
+		nodes:numbers[0],
+		edges:numbers[1],
+		vertex_sizes:flags[0],
+		vertex_weights:if flags[1] {constraints} else {0},
+		edge_weights:flags[2]
+	})
+}
+
+//Reads a METIS graph file. Line i after the header lists the neighbours of node i, so every
+//undirected edge is listed from both ends, and the tree is symmetric whatever the options
+//say. Vertex sizes and weights are skipped. With weighted the edge weights (fmt xx1) become
+//the cell values.
+fn parse_metis<W,F>(filename:&str,background:W,weighted:bool,options:&ReadOptions,value:F)->Result<K2tree<W>,GraphFormatError>
+where W:Eq + Clone,F:Fn(Option<&str>)->Option<W>{
+	let mut header:Option<MetisHeader> = None;
+	let mut node = 0;
+	let mut entries = Vec::new();
+	//Line of every entry, for the symmetry check
+	let mut lines = Vec::new();
+	for (number,line) in open_reader(filename)?.lines().enumerate(){
+		let line = line?;
+		if line.trim_start().starts_with('%'){
+			continue;
+		}
+		let header = match &header{
+			None => {
+				if line.trim().is_empty(){
+					continue;
+				}
+				let parsed = parse_metis_header(&line)?;
+				if weighted && !parsed.edge_weights{
+					return Err(GraphFormatError::Header(String::from("graph has no edge weights")));
+				}
+				header = Some(parsed);
+				continue;
+			},
+			Some(header) => header
+		};
+		//Empty lines are nodes without neighbours
+		if node >= header.nodes{
+			if line.trim().is_empty(){
+				continue;
+			}
+			return Err(parse_error(number+1,String::from("more nodes than declared")));
+		}
+		let skip = header.vertex_sizes as usize + header.vertex_weights;
+		let tokens:Vec<&str> = line.split_whitespace().skip(skip).collect();
+		let step = if header.edge_weights {2} else {1};
+		if tokens.len() % step != 0{
+			return Err(parse_error(number+1,String::from("neighbour without weight")));
+		}
+		for pair in tokens.chunks(step){
+			let neighbour = parse_node(pair[0],header.nodes,number+1)?;
+			let token = if weighted {pair.get(1).copied()} else {None};
+			let cell = value(token).ok_or_else(|| parse_error(number+1,format!("invalid weight {}",token.unwrap_or(""))))?;
+			if cell == background{
+				return Err(parse_error(number+1,format!("weight {} is the background",token.unwrap_or(""))));
+			}
+			entries.push((node,neighbour,cell));
+			lines.push(number+1);
+		}
+		node+=1;
+	}
+	let header = header.ok_or_else(|| GraphFormatError::Header(String::from("missing header")))?;
+	if node != header.nodes{
+		return Err(GraphFormatError::Header(format!("declared {} nodes, found {}",header.nodes,node)));
+	}
+	check_symmetric(&entries,&lines)?;
+	if entries.len() != 2*header.edges{
+		return Err(GraphFormatError::Header(format!("declared {} edges, found {} adjacencies",header.edges,entries.len())));
+	}
+	Ok(options.build(header.nodes,header.nodes,background,true,entries))
+}
+
+//Every adjacency (u,v) must be listed by v too, with the same weight
+fn check_symmetric<W>(entries:&[(usize,usize,W)],lines:&[usize])->Result<(),GraphFormatError> where W:Eq{
+	let mut sorted:Vec<(usize,usize,usize)> = entries.iter().enumerate().map(|(e,&(u,v,_))| (u,v,e)).collect();
+	sorted.sort_unstable();
+	for (e,(u,v,cell)) in entries.iter().enumerate(){
+		let found = sorted.partition_point(|&(a,b,_)| (a,b) < (*v,*u));
+		match sorted.get(found){
+			Some(&(a,b,mirror)) if (a,b) == (*v,*u) => if entries[mirror].2 != *cell{
+				return Err(parse_error(lines[e],format!("weight of {}-{} differs from {}-{}",u+1,v+1,v+1,u+1)));
+			},
+			_ => return Err(parse_error(lines[e],format!("node {} lists {} but {} does not list {}",u+1,v+1,v+1,u+1)))
+		}
+	}
+	Ok(())
+}
+
+pub fn read_metis(filename:&str)->Result<K2tree<bool>,GraphFormatError>{
+	read_metis_with(filename,&ReadOptions::default())
+}
+
+pub fn read_metis_with(filename:&str,options:&ReadOptions)->Result<K2tree<bool>,GraphFormatError>{
+	parse_metis(filename,false,false,options,|_| Some(true))
+}
+
+pub fn read_metis_weighted<W>(filename:&str,background:W)->Result<K2tree<W>,GraphFormatError>
+where W:FromStr + Eq + Clone{
+	read_metis_weighted_with(filename,background,&ReadOptions::default())
+}
+
+pub fn read_metis_weighted_with<W>(filename:&str,background:W,options:&ReadOptions)->Result<K2tree<W>,GraphFormatError>
+where W:FromStr + Eq + Clone{
+	parse_metis(filename,background,true,options,|token| token.and_then(|token| token.parse::<W>().ok()))
+}
+
+//Reads a DIMACS shortest path file ("p sp n m" and "a u v w" lines). Arc weights are the
+//values of the cells, repeated arcs keep the last weight. An arc weighing the background
+//could not be told from a missing arc, so it is an error: pick a background no arc uses.
+pub fn read_dimacs<W>(filename:&str,background:W)->Result<K2tree<W>,GraphFormatError>
+where W:FromStr + Eq + Clone{
+	read_dimacs_with(filename,background,&ReadOptions::default())
+}
+
+pub fn read_dimacs_with<W>(filename:&str,background:W,options:&ReadOptions)->Result<K2tree<W>,GraphFormatError>
+where W:FromStr + Eq + Clone{
+	let mut size:Option<(usize,usize)> = None;
+	let mut arcs = Vec::new();
+	for (number,line) in open_reader(filename)?.lines().enumerate(){
+		let line = line?;
+		let tokens:Vec<&str> = line.split_whitespace().collect();
+		match tokens.first().copied(){
+			None | Some("c") => continue,
+			Some("p") => {
+				if size.is_some(){
+					return Err(parse_error(number+1,String::from("repeated problem line")));
+				}
+				if tokens.len() != 4 || tokens[1] != "sp"{
+					return Err(GraphFormatError::Header(line.clone()));
+				}
+				let nodes = tokens[2].parse::<usize>().map_err(|_| GraphFormatError::Header(line.clone()))?;
+				let edges = tokens[3].parse::<usize>().map_err(|_| GraphFormatError::Header(line.clone()))?;
+				if nodes == 0{
+					return Err(GraphFormatError::Header(line.clone()));
+				}
+				size = Some((nodes,edges));
+			},
+			Some("a") => {
+				let (nodes,_) = size.ok_or_else(|| parse_error(number+1,String::from("arc before problem line")))?;
+				if tokens.len() != 4{
+					return Err(parse_error(number+1,String::from("expected a u v w")));
+				}
+				let from = parse_node(tokens[1],nodes,number+1)?;
+				let to = parse_node(tokens[2],nodes,number+1)?;
+				let weight = tokens[3].parse::<W>().map_err(|_| parse_error(number+1,format!("invalid weight {}",tokens[3])))?;
+				if weight == background{
+					return Err(parse_error(number+1,format!("weight {} is the background",tokens[3])));
+				}
+				arcs.push((from,to,weight));
+			},
+			Some(other) => return Err(parse_error(number+1,format!("unknown line type {}",other)))
+		}
+	}
+	let (nodes,edges) = size.ok_or_else(|| GraphFormatError::Header(String::from("missing problem line")))?;
+	if arcs.len() != edges{
+		return Err(GraphFormatError::Header(format!("declared {} arcs, found {}",edges,arcs.len())));
+	}
+	Ok(options.build(nodes,nodes,background,false,arcs))
+}
+
+#[cfg(test)]
+mod tests{
+	use std::fs::File;
+	use std::io::Write;
+	use super::{read_metis,read_metis_with,read_metis_weighted,read_dimacs,read_dimacs_with,GraphFormatError};
+	use crate::webgraph::ReadOptions;
+
+	fn write_temp(name:&str,content:&str)->String{
+		let path = std::env::temp_dir().join(format!("k2tree_{}_{}",std::process::id(),name));
+		File::create(&path).unwrap().write_all(content.as_bytes()).unwrap();
+		path.to_str().unwrap().to_string()
+	}
+
+	#[test]
+	fn metis_unweighted(){
+		//Triangle 1-2-3 plus an isolated node 4
+		let path = write_temp("graph.metis","% comment\n4 3\n2 3\n1 3\n1 2\n\n");
+		let k2tree = read_metis(&path).unwrap();
+		assert!(k2tree.is_symmetric());
+		assert_eq!(k2tree.get_rows(),4);
+		assert_eq!(k2tree.row(0).into_iter().map(|(j,_)| j).collect::<Vec<_>>(),vec![1,2]);
+		assert!(*k2tree.get(2,1).unwrap());
+		assert!(k2tree.row(3).is_empty());
+		let looped = write_temp("looped.metis","2 2\n1 2\n1 2\n");
+		assert!(*read_metis(&looped).unwrap().get(0,0).unwrap());
+		let k2tree = read_metis_with(&looped,&ReadOptions::new().drop_self_loops(true)).unwrap();
+		assert!(!*k2tree.get(0,0).unwrap());
+		assert!(*k2tree.get(1,0).unwrap());
+	}
+
+	#[test]
+	fn metis_weights(){
+		//fmt 011: one vertex weight, then neighbour/weight pairs
+		let path = write_temp("weighted.metis","3 2 011\n5 2 7\n1 1 7 3 4\n2 2 4\n");
+		let k2tree = read_metis_weighted(&path,0u32).unwrap();
+		assert_eq!(*k2tree.get(0,1).unwrap(),7);
+		assert_eq!(*k2tree.get(1,0).unwrap(),7);
+		assert_eq!(*k2tree.get(2,1).unwrap(),4);
+		assert!(*read_metis(&path).unwrap().get(1,2).unwrap());
+		let unweighted = write_temp("unweighted.metis","2 1\n2\n1\n");
+		assert!(matches!(read_metis_weighted(&unweighted,0u32),Err(GraphFormatError::Header(_))));
+	}
+
+	#[test]
+	fn metis_errors(){
+		let path = write_temp("overflow.metis","2 1\n3\n1\n");
+		assert!(matches!(read_metis(&path),Err(GraphFormatError::Parse(_))));
+		let path = write_temp("missing.metis","3 1\n2\n1\n");
+		assert!(matches!(read_metis(&path),Err(GraphFormatError::Header(_))));
+		let path = write_temp("empty.metis","0 0\n");
+		assert!(matches!(read_metis(&path),Err(GraphFormatError::Header(_))));
+		let path = write_temp("fmt.metis","2 1 2\n2\n1\n");
+		assert!(matches!(read_metis(&path),Err(GraphFormatError::Header(_))));
+		let path = write_temp("zero_weight.metis","2 1 1\n2 0\n1 0\n");
+		assert!(matches!(read_metis_weighted(&path,0u32),Err(GraphFormatError::Parse(_))));
+		//3 lists 1 but 1 does not list 3, then 1-2 and 2-1 weigh differently
+		let path = write_temp("asymmetric.metis","3 2\n2\n1\n1 2\n");
+		match read_metis(&path){
+			Err(GraphFormatError::Parse(error)) => {
+				assert_eq!(error.line,4);
+				assert_eq!(error.message,"node 3 lists 1 but 1 does not list 3");
+			},
+			_ => panic!("expected an asymmetric entry")
+		}
+		let path = write_temp("asymmetric_weights.metis","2 1 1\n2 5\n1 6\n");
+		match read_metis_weighted(&path,0u32){
+			Err(GraphFormatError::Parse(error)) => assert_eq!(error.line,2),
+			_ => panic!("expected different weights")
+		}
+		assert!(read_metis(&path).is_ok());
+	}
+
+	#[test]
+	fn dimacs_weights(){
+		let path = write_temp("graph.gr","c 9th DIMACS\np sp 3 3\na 1 2 10\na 2 3 0\na 3 1 25\n");
+		let k2tree = read_dimacs(&path,u64::MAX).unwrap();
+		assert_eq!(*k2tree.get(0,1).unwrap(),10);
+		assert_eq!(*k2tree.get(1,2).unwrap(),0);
+		assert_eq!(*k2tree.get(2,0).unwrap(),25);
+		assert_eq!(*k2tree.get(1,0).unwrap(),u64::MAX);
+		assert_eq!(k2tree.range(0..=2,0..=2).len(),3);
+		let k2tree = read_dimacs_with(&path,u64::MAX,&ReadOptions::new().symmetrize(true)).unwrap();
+		assert!(k2tree.is_symmetric());
+		assert_eq!(*k2tree.get(1,0).unwrap(),10);
+		let path = write_temp("loop.gr","p sp 2 2\na 1 1 3\na 1 2 4\n");
+		let k2tree = read_dimacs_with(&path,0u64,&ReadOptions::new().drop_self_loops(true)).unwrap();
+		assert_eq!(k2tree.range(0..=1,0..=1),vec![((0,1),&4)]);
+	}
+
+	#[test]
+	fn dimacs_errors(){
+		let path = write_temp("noproblem.gr","a 1 2 3\n");
+		assert!(matches!(read_dimacs(&path,0u64),Err(GraphFormatError::Parse(_))));
+		let path = write_temp("count.gr","p sp 2 2\na 1 2 3\n");
+		assert!(matches!(read_dimacs(&path,0u64),Err(GraphFormatError::Header(_))));
+		let path = write_temp("weight.gr","p sp 2 1\na 1 2 x\n");
+		assert!(matches!(read_dimacs(&path,0u64),Err(GraphFormatError::Parse(_))));
+		//Weight 0 would vanish into a background of 0
+		let path = write_temp("zero.gr","p sp 2 1\na 1 2 0\n");
+		match read_dimacs(&path,0u64){
+			Err(GraphFormatError::Parse(error)) => assert_eq!(error.line,2),
+			_ => panic!("expected a parse error")
+		}
+		assert_eq!(*read_dimacs(&path,u64::MAX).unwrap().get(0,1).unwrap(),0);
+	}
+}
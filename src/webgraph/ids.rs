@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::k2tree::K2tree;
+
+//Bidirectional mapping between the node labels of a file and the dense indices of the tree
+#[derive(Debug,Clone)]
+pub struct IdMap<L>{
+	labels:Vec<L>,
+	index:HashMap<L,usize>
+}
+
+impl <L> Default for IdMap<L>{
+	fn default()->Self{
+		IdMap{
+			labels:Vec::new(),
+			index:HashMap::new()
+		}
+	}
+}
+
+impl <L> IdMap<L> where L:Hash + Eq + Clone{
+	pub fn new()->Self{
+		Self::default()
+	}
+	//Labels get indices in order of first appearance
+	pub fn get_or_insert(&mut self,label:L)->usize{
+		if let Some(&e) = self.index.get(&label){
+			return e;
+		}
+		let e = self.labels.len();
+		self.index.insert(label.clone(),e);
+		self.labels.push(label);
+		e
+	}
+	pub fn index_of(&self,label:&L)->Option<usize>{
+		self.index.get(label).copied()
+	}
+	pub fn label_of(&self,index:usize)->Option<&L>{
+		self.labels.get(index)
+	}
+	pub fn len(&self)->usize{
+		self.labels.len()
+	}
+	pub fn is_empty(&self)->bool{
+		self.labels.is_empty()
+	}
+	pub fn get_labels(&self)->&Vec<L>{
+		&self.labels
+	}
+}
+
+//Graph whose queries are answered in the labels of the original file
+#[derive(Debug)]
+pub struct LabelledGraph<L>{
+	tree:K2tree<bool>,
+	ids:IdMap<L>
+}
+
+impl <L> LabelledGraph<L> where L:Hash + Eq + Clone{
+	pub fn new(tree:K2tree<bool>,ids:IdMap<L>)->Self{
+		assert!(tree.get_rows() >= ids.len() && tree.get_cols() >= ids.len(),"mapping has more nodes than the tree");
+		LabelledGraph{
+			tree,
+			ids
+		}
+	}
+	pub fn has_edge(&self,from:&L,to:&L)->bool{
+		match (self.ids.index_of(from),self.ids.index_of(to)){
+			(Some(i),Some(j)) => *self.tree.get(i,j).unwrap(),
+			_ => false
+		}
+	}
+	pub fn successors(&self,from:&L)->Vec<&L>{
+		match self.ids.index_of(from){
+			Some(i) => self.tree.row(i).into_iter().map(|(j,_)| self.ids.label_of(j).unwrap()).collect(),
+			None => Vec::new()
+		}
+	}
+	pub fn predecessors(&self,to:&L)->Vec<&L>{
+		match self.ids.index_of(to){
+			Some(j) => self.tree.column(j).into_iter().map(|(i,_)| self.ids.label_of(i).unwrap()).collect(),
+			None => Vec::new()
+		}
+	}
+	pub fn get_tree(&self)->&K2tree<bool>{
+		&self.tree
+	}
+	pub fn get_ids(&self)->&IdMap<L>{
+		&self.ids
+	}
+	pub fn into_parts(self)->(K2tree<bool>,IdMap<L>){
+		(self.tree,self.ids)
+	}
+}
+
+#[cfg(test)]
+mod tests{
+	use super::IdMap;
+
+	#[test]
+	fn id_map_roundtrip(){
+		let mut ids = IdMap::new();
+		assert_eq!(ids.get_or_insert(9_000_000_000u64),0);
+		assert_eq!(ids.get_or_insert(3),1);
+		assert_eq!(ids.get_or_insert(9_000_000_000),0);
+		assert_eq!(ids.len(),2);
+		assert_eq!(ids.index_of(&3),Some(1));
+		assert_eq!(ids.label_of(0),Some(&9_000_000_000));
+		assert_eq!(ids.index_of(&4),None);
+	}
+}
@@ -3,8 +3,26 @@
 use crate::matrix::Matrix;
 
 use std::fs::File;
+use std::hash::Hash;
 use std::io::{self, BufRead};
 use std::path::Path;
+use std::str::FromStr;
+
+use flate2::read::GzDecoder;
+
+pub mod ids;
+pub mod edgelist;
+pub mod mtx;
+pub mod formats;
+pub mod writer;
+pub mod options;
+
+pub use ids::{IdMap,LabelledGraph};
+pub use edgelist::{EdgeListReader,EdgeListError,LineError,Delimiter};
+pub use mtx::{read_mtx,read_mtx_with,write_mtx,write_mtx_to,MtxValue,MtxField,MtxError};
+pub use formats::{read_metis,read_metis_with,read_metis_weighted,read_metis_weighted_with,read_dimacs,read_dimacs_with,GraphFormatError};
+pub use writer::{write_edge_list,write_adjacency_list,WriteOptions};
+pub use options::ReadOptions;
 
 fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
 where P: AsRef<Path>, {
@@ -12,7 +30,25 @@ where P: AsRef<Path>, {
     Ok(io::BufReader::new(file).lines())
 }
 
+//Opens plain or gzip compressed files, telling them apart by the gzip magic bytes
+pub(crate) fn open_reader<P>(filename: P) -> io::Result<Box<dyn BufRead>>
+where P: AsRef<Path>, {
+	let mut reader = io::BufReader::new(File::open(filename)?);
+	let gzip = reader.fill_buf()?.starts_with(&[0x1f,0x8b]);
+	if gzip{
+		Ok(Box::new(io::BufReader::new(GzDecoder::new(reader))))
+	}else{
+		Ok(Box::new(reader))
+	}
+}
+
 pub fn from_file(filename: &str,nodes:usize) -> Matrix<bool>{
+	from_file_with(filename,nodes,&ReadOptions::default())
+}
+
+//Symmetrize sets both (from,to) and (to,from). The matrix has no k, that is up to the tree
+//built from it.
+pub fn from_file_with(filename: &str,nodes:usize,options:&ReadOptions) -> Matrix<bool>{
 	let mut matrix = Matrix::new(nodes,nodes);
     if let Ok(lines) = read_lines(filename) {
         for line in lines {
@@ -21,7 +57,13 @@ pub fn from_file(filename: &str,nodes:usize) -> Matrix<bool>{
 					let mut splitted = line.split("\t");
 					let from = splitted.next().unwrap();
 					let to = splitted.next().unwrap();
-					matrix.set(from.parse::<usize>().unwrap(),to.parse::<usize>().unwrap(),true);
+					let (from,to) = (from.parse::<usize>().unwrap(),to.parse::<usize>().unwrap());
+					if options.keeps(from,to){
+						matrix.set(from,to,true);
+						if options.get_symmetrize(){
+							matrix.set(to,from,true);
+						}
+					}
 				
 				}
             }
@@ -29,10 +71,118 @@ pub fn from_file(filename: &str,nodes:usize) -> Matrix<bool>{
     }
 	matrix
 }
+fn invalid_line(line:usize,message:String)->io::Error{
+	io::Error::new(io::ErrorKind::InvalidData,format!("line {}: {}",line,message))
+}
+
+//Reads an edge list without knowing the node count. Labels can be any FromStr type (huge
+//numeric ids, strings...) and get dense indices in order of first appearance.
+pub fn from_file_remapped<L>(filename: &str) -> io::Result<LabelledGraph<L>>
+where L: FromStr + Hash + Eq + Clone, {
+	from_file_remapped_with(filename,&ReadOptions::default())
+}
+
+//Nodes only seen in dropped self loops still get an index
+pub fn from_file_remapped_with<L>(filename: &str,options:&ReadOptions) -> io::Result<LabelledGraph<L>>
+where L: FromStr + Hash + Eq + Clone, {
+	let mut ids = IdMap::new();
+	let mut edges = Vec::new();
+	for (number,line) in read_lines(filename)?.enumerate(){
+		let line = line?;
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') || line.starts_with('%'){
+			continue;
+		}
+		let mut splitted = line.split_whitespace();
+		let (from,to) = match (splitted.next(),splitted.next()){
+			(Some(from),Some(to)) => (from,to),
+			_ => return Err(invalid_line(number+1,String::from("expected two node ids")))
+		};
+		let from = from.parse::<L>().map_err(|_| invalid_line(number+1,format!("invalid node id {}",from)))?;
+		let to = to.parse::<L>().map_err(|_| invalid_line(number+1,format!("invalid node id {}",to)))?;
+		edges.push((ids.get_or_insert(from),ids.get_or_insert(to)));
+	}
+	if ids.is_empty(){
+		return Err(io::Error::new(io::ErrorKind::InvalidData,"graph has no edges"));
+	}
+	let nodes = ids.len();
+	let tree = options.build(nodes,nodes,false,false,edges.into_iter().map(|(i,j)| (i,j,true)));
+	Ok(LabelledGraph::new(tree,ids))
+}
+
 #[cfg(test)]
 mod tests{
 	use crate::k2tree::K2tree;
 	use super::*;
+	use std::io::Write;
+
+	fn write_temp(name:&str,content:&str)->String{
+		let path = std::env::temp_dir().join(format!("k2tree_{}_{}",std::process::id(),name));
+		let mut file = File::create(&path).unwrap();
+		file.write_all(content.as_bytes()).unwrap();
+		path.to_str().unwrap().to_string()
+	}
+
+	#[test]
+	fn file_with_options(){
+		let path = write_temp("tabs.txt","# from\tto\n0\t1\n2\t2\n3\t1\n");
+		let matrix = from_file(&path,4);
+		assert!(*matrix.get(2,2).unwrap());
+		assert!(!*matrix.get(1,0).unwrap());
+		let options = ReadOptions::new().symmetrize(true).drop_self_loops(true);
+		assert!(options.get_symmetrize() && options.get_drop_self_loops());
+		let matrix = from_file_with(&path,4,&options);
+		assert!(!*matrix.get(2,2).unwrap());
+		assert!(*matrix.get(1,0).unwrap());
+		assert!(*matrix.get(1,3).unwrap());
+		assert_eq!(matrix.iter().filter(|&&cell| cell).count(),4);
+	}
+
+	#[test]
+	fn remapped_numeric_ids(){
+		let path = write_temp("numeric.txt","# FromNodeId\tToNodeId\n9000000000\t17\n17\t42\n\n42 9000000000\n");
+		let graph = from_file_remapped::<u64>(&path).unwrap();
+		assert_eq!(graph.get_ids().len(),3);
+		assert_eq!(graph.get_tree().get_rows(),3);
+		assert!(graph.has_edge(&9000000000,&17));
+		assert!(graph.has_edge(&42,&9000000000));
+		assert!(!graph.has_edge(&17,&9000000000));
+		assert!(!graph.has_edge(&1,&17));
+		assert_eq!(graph.successors(&17),vec![&42]);
+		assert_eq!(graph.predecessors(&9000000000),vec![&42]);
+	}
+
+	#[test]
+	fn remapped_string_ids(){
+		let path = write_temp("strings.txt","alice\tbob\nbob\tcarol\nalice\tcarol\n");
+		let graph = from_file_remapped::<String>(&path).unwrap();
+		let alice = String::from("alice");
+		let mut successors = graph.successors(&alice);
+		successors.sort();
+		assert_eq!(successors,vec!["bob","carol"]);
+		assert_eq!(graph.get_ids().index_of(&String::from("carol")),Some(2));
+	}
+
+	#[test]
+	fn remapped_options(){
+		let path = write_temp("undirected.txt","a b\nb c\nc c\n");
+		let options = ReadOptions::new().symmetrize(true).drop_self_loops(true);
+		let graph = from_file_remapped_with::<String>(&path,&options).unwrap();
+		assert!(graph.get_tree().is_symmetric());
+		assert!(graph.has_edge(&String::from("b"),&String::from("a")));
+		assert!(!graph.has_edge(&String::from("c"),&String::from("c")));
+		assert_eq!(graph.get_ids().len(),3);
+	}
+
+	#[test]
+	fn remapped_malformed_line(){
+		let path = write_temp("malformed.txt","1\t2\n3\n");
+		let error = from_file_remapped::<u64>(&path).unwrap_err();
+		assert_eq!(error.kind(),io::ErrorKind::InvalidData);
+		assert!(error.to_string().contains("line 2"));
+		let path = write_temp("not_numeric.txt","1\tx\n");
+		assert!(from_file_remapped::<u64>(&path).is_err());
+	}
 
 	#[test]
 	fn test(){
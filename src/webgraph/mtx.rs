@@ -0,0 +1,330 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+
+use super::open_reader;
+use super::options::ReadOptions;
+use super::edgelist::LineError;
+use crate::k2tree::K2tree;
+use crate::real::Real;
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum MtxField{
+	Pattern,
+	Integer,
+	Real
+}
+
+#[derive(Debug)]
+pub enum MtxError{
+	Io(io::Error),
+	Header(String),
+	Parse(LineError),
+	Unsupported(String)
+}
+
+impl fmt::Display for MtxError{
+	fn fmt(&self,f:&mut fmt::Formatter<'_>)->fmt::Result{
+		match self{
+			MtxError::Io(error) => write!(f,"{}",error),
+			MtxError::Header(message) => write!(f,"invalid header: {}",message),
+			MtxError::Parse(error) => write!(f,"{}",error),
+			MtxError::Unsupported(message) => write!(f,"unsupported matrix market file: {}",message)
+		}
+	}
+}
+
+impl std::error::Error for MtxError{}
+
+impl From<io::Error> for MtxError{
+	fn from(error:io::Error)->Self{
+		MtxError::Io(error)
+	}
+}
+
+//Values that can be stored in and read from a coordinate Matrix Market file
+pub trait MtxValue: Sized + Eq + Clone{
+	//Field written in the header
+	const FIELD:MtxField;
+	//Value of the entries that are not listed
+	fn zero()->Self;
+	//token is None for pattern files
+	fn parse(field:MtxField,token:Option<&str>)->Result<Self,String>;
+	fn format(&self)->String;
+}
+
+impl MtxValue for bool{
+	const FIELD:MtxField = MtxField::Pattern;
+	fn zero()->Self{
+		false
+	}
+	fn parse(_field:MtxField,token:Option<&str>)->Result<Self,String>{
+		match token{
+			None => Ok(true),
+			Some(token) => token.parse::<f64>().map(|value| value != 0.0).map_err(|_| format!("invalid value {}",token))
+		}
+	}
+	fn format(&self)->String{
+		String::new()
+	}
+}
+
+impl MtxValue for Real{
+	const FIELD:MtxField = MtxField::Real;
+	fn zero()->Self{
+		Real(0.0)
+	}
+	fn parse(_field:MtxField,token:Option<&str>)->Result<Self,String>{
+		match token{
+			None => Ok(Real(1.0)),
+			//-0.0 is stored as 0.0, so it is never written back with its sign
+			Some(token) => token.parse::<f64>().map(|value| Real(if value == 0.0 {0.0} else {value})).map_err(|_| format!("invalid value {}",token))
+		}
+	}
+	fn format(&self)->String{
+		format!("{}",self.0)
+	}
+}
+
+macro_rules! integer_mtx_value{
+	($($t:ty),*) => {$(
+		impl MtxValue for $t{
+			const FIELD:MtxField = MtxField::Integer;
+			fn zero()->Self{
+				0
+			}
+			fn parse(field:MtxField,token:Option<&str>)->Result<Self,String>{
+				match (field,token){
+					(_,None) => Ok(1),
+					(MtxField::Real,Some(token)) => Err(format!("real value {} in an integer matrix",token)),
+					(_,Some(token)) => token.parse::<$t>().map_err(|_| format!("invalid value {}",token))
+				}
+			}
+			fn format(&self)->String{
+				self.to_string()
+			}
+		}
+	)*}
+}
+
+integer_mtx_value!(i32,i64,u32,u64,usize);
+
+struct Header{
+	field:MtxField,
+	symmetric:bool
+}
+
+fn parse_header(line:&str)->Result<Header,MtxError>{
+	let tokens:Vec<String> = line.split_whitespace().map(|token| token.to_lowercase()).collect();
+	if tokens.len() != 5 || tokens[0] != "%%matrixmarket"{
+		return Err(MtxError::Header(String::from(line)));
+	}
+	if tokens[1] != "matrix"{
+		return Err(MtxError::Unsupported(format!("object {}",tokens[1])));
+	}
+	if tokens[2] != "coordinate"{
+		return Err(MtxError::Unsupported(format!("format {}",tokens[2])));
+	}
+	let field = match tokens[3].as_str(){
+		"pattern" => MtxField::Pattern,
+		"integer" => MtxField::Integer,
+		"real" => MtxField::Real,
+		other => return Err(MtxError::Unsupported(format!("field {}",other)))
+	};
+	let symmetric = match tokens[4].as_str(){
+		"general" => false,
+		"symmetric" => true,
+		other => return Err(MtxError::Unsupported(format!("symmetry {}",other)))
+	};
+	Ok(Header{field,symmetric})
+}
+
+//Entries reserved up front at most, the size line is not trusted beyond this
+const MAX_RESERVE:usize = 1<<20;
+
+fn parse_error(line:usize,message:String)->MtxError{
+	MtxError::Parse(LineError{line,message})
+}
+
+fn parse_index(token:Option<&str>,limit:usize,line:usize)->Result<usize,MtxError>{
+	let token = token.ok_or_else(|| parse_error(line,String::from("missing index")))?;
+	match token.parse::<usize>(){
+		Ok(index) if index >= 1 && index <= limit => Ok(index-1),
+		_ => Err(parse_error(line,format!("invalid index {}",token)))
+	}
+}
+
+//Reads a coordinate matrix. Symmetric files give a symmetric tree, which keeps the stored
+//triangle only and mirrors it on queries.
+pub fn read_mtx<T>(filename:&str)->Result<K2tree<T>,MtxError> where T:MtxValue{
+	read_mtx_with(filename,&ReadOptions::default())
+}
+
+pub fn read_mtx_with<T>(filename:&str,options:&ReadOptions)->Result<K2tree<T>,MtxError> where T:MtxValue{
+	let mut lines = open_reader(filename)?.lines().enumerate();
+	let header = match lines.next(){
+		Some((_,line)) => parse_header(&line?)?,
+		None => return Err(MtxError::Header(String::from("empty file")))
+	};
+	let mut size:Option<(usize,usize,usize)> = None;
+	let mut entries = Vec::new();
+	for (number,line) in lines{
+		let line = line?;
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('%'){
+			continue;
+		}
+		let mut tokens = line.split_whitespace();
+		let (rows,cols,nnz) = match size{
+			None => {
+				let values:Vec<usize> = tokens.map(|token| token.parse::<usize>()).collect::<Result<_,_>>()
+					.map_err(|_| parse_error(number+1,String::from("invalid size line")))?;
+				if values.len() != 3 || values[0] == 0 || values[1] == 0{
+					return Err(parse_error(number+1,String::from("invalid size line")));
+				}
+				size = Some((values[0],values[1],values[2]));
+				entries.reserve(std::cmp::min(values[2],MAX_RESERVE));
+				continue;
+			},
+			Some(size) => size
+		};
+		if entries.len() >= nnz{
+			return Err(parse_error(number+1,String::from("more entries than declared")));
+		}
+		let i = parse_index(tokens.next(),rows,number+1)?;
+		let j = parse_index(tokens.next(),cols,number+1)?;
+		let token = match header.field{
+			MtxField::Pattern => None,
+			_ => Some(tokens.next().ok_or_else(|| parse_error(number+1,String::from("missing value")))?)
+		};
+		let value = T::parse(header.field,token).map_err(|message| parse_error(number+1,message))?;
+		entries.push((i,j,value));
+	}
+	let (rows,cols,nnz) = size.ok_or_else(|| MtxError::Header(String::from("missing size line")))?;
+	if entries.len() != nnz{
+		return Err(MtxError::Header(format!("declared {} entries, found {}",nnz,entries.len())));
+	}
+	if (header.symmetric || options.get_symmetrize()) && rows != cols{
+		return Err(MtxError::Header(String::from("symmetric matrix must be square")));
+	}
+	Ok(options.build(rows,cols,T::zero(),header.symmetric,entries))
+}
+
+//Writes the non-zero cells as a general coordinate matrix. The tree is walked twice, once to
+//count the entries for the size line and once to stream them row by row.
+pub fn write_mtx_to<T,W>(k2tree:&K2tree<T>,writer:&mut W)->io::Result<()> where T:MtxValue,W:Write{
+	let zero = T::zero();
+	let rows = k2tree.get_rows();
+	let cols = k2tree.get_cols();
+	let mut nnz = 0;
+	k2tree.walk(0..=rows-1,0..=cols-1,&zero,&mut |_,_,_| nnz+=1);
+	let field = match T::FIELD{
+		MtxField::Pattern => "pattern",
+		MtxField::Integer => "integer",
+		MtxField::Real => "real"
+	};
+	writeln!(writer,"%%MatrixMarket matrix coordinate {} general",field)?;
+	writeln!(writer,"{} {} {}",rows,cols,nnz)?;
+	for i in 0..rows{
+		for (j,value) in k2tree.row_with(i,&zero){
+			match T::FIELD{
+				MtxField::Pattern => writeln!(writer,"{} {}",i+1,j+1)?,
+				_ => writeln!(writer,"{} {} {}",i+1,j+1,value.format())?
+			}
+		}
+	}
+	Ok(())
+}
+
+pub fn write_mtx<T>(k2tree:&K2tree<T>,filename:&str)->io::Result<()> where T:MtxValue{
+	let mut writer = BufWriter::new(File::create(filename)?);
+	write_mtx_to(k2tree,&mut writer)?;
+	writer.flush()
+}
+
+#[cfg(test)]
+mod tests{
+	use std::fs::File;
+	use std::io::Write;
+	use super::{read_mtx,read_mtx_with,write_mtx,write_mtx_to,MtxError};
+	use crate::real::Real;
+	use crate::webgraph::ReadOptions;
+
+	fn write_temp(name:&str,content:&str)->String{
+		let path = std::env::temp_dir().join(format!("k2tree_{}_{}",std::process::id(),name));
+		File::create(&path).unwrap().write_all(content.as_bytes()).unwrap();
+		path.to_str().unwrap().to_string()
+	}
+
+	#[test]
+	fn pattern_general(){
+		let path = write_temp("pattern.mtx","%%MatrixMarket matrix coordinate pattern general\n% comment\n3 4 3\n1 2\n3 4\n2 1\n");
+		let k2tree = read_mtx::<bool>(&path).unwrap();
+		assert_eq!((k2tree.get_rows(),k2tree.get_cols()),(3,4));
+		assert_eq!(k2tree.range(0..=2,0..=3).len(),3);
+		assert!(*k2tree.get(0,1).unwrap());
+		assert!(*k2tree.get(2,3).unwrap());
+		assert!(!*k2tree.get(1,2).unwrap());
+	}
+
+	#[test]
+	fn integer_symmetric(){
+		let path = write_temp("symmetric.mtx","%%MatrixMarket matrix coordinate integer symmetric\n3 3 3\n1 1 4\n3 1 -2\n3 2 7\n");
+		let k2tree = read_mtx::<i64>(&path).unwrap();
+		assert!(k2tree.is_symmetric());
+		assert_eq!(k2tree.range(0..=2,0..=2).len(),5);
+		assert_eq!(*k2tree.get(0,0).unwrap(),4);
+		assert_eq!(*k2tree.get(2,0).unwrap(),-2);
+		assert_eq!(*k2tree.get(0,2).unwrap(),-2);
+		assert_eq!(*k2tree.get(1,2).unwrap(),7);
+		let options = ReadOptions::new().drop_self_loops(true);
+		let k2tree = read_mtx_with::<i64>(&path,&options).unwrap();
+		assert_eq!(*k2tree.get(0,0).unwrap(),0);
+		assert_eq!(*k2tree.get(0,2).unwrap(),-2);
+		let general = write_temp("general.mtx","%%MatrixMarket matrix coordinate pattern general\n3 3 1\n3 1\n");
+		let k2tree = read_mtx_with::<bool>(&general,&ReadOptions::new().symmetrize(true)).unwrap();
+		assert!(k2tree.is_symmetric());
+		assert!(*k2tree.get(0,2).unwrap());
+		let wide = write_temp("wide.mtx","%%MatrixMarket matrix coordinate pattern general\n2 3 1\n1 3\n");
+		assert!(matches!(read_mtx_with::<bool>(&wide,&ReadOptions::new().symmetrize(true)),Err(MtxError::Header(_))));
+		assert!(matches!(read_mtx::<i64>(&write_temp("real_as_int.mtx","%%MatrixMarket matrix coordinate real general\n1 1 1\n1 1 2.5\n")),Err(MtxError::Parse(_))));
+	}
+
+	#[test]
+	fn real_roundtrip(){
+		let path = write_temp("real.mtx","%%MatrixMarket matrix coordinate real general\n2 3 2\n1 3 2.5\n2 1 -1e-3\n");
+		let k2tree = read_mtx::<Real>(&path).unwrap();
+		assert_eq!(*k2tree.get(0,2).unwrap(),Real(2.5));
+		let output = write_temp("real_out.mtx","");
+		write_mtx(&k2tree,&output).unwrap();
+		let again = read_mtx::<Real>(&output).unwrap();
+		assert_eq!(again.to_coo(),k2tree.to_coo());
+		assert_eq!(Real(-0.0),Real(0.0));
+		let path = write_temp("signed_zero.mtx","%%MatrixMarket matrix coordinate real general\n2 2 2\n1 1 -0.0\n2 2 -0\n");
+		let k2tree = read_mtx::<Real>(&path).unwrap();
+		assert!(k2tree.range(0..=1,0..=1).is_empty());
+		assert_eq!(k2tree.get(0,0).unwrap().0.to_bits(),0.0f64.to_bits());
+	}
+
+	#[test]
+	fn writer_streams_rows(){
+		let path = write_temp("write.mtx","%%MatrixMarket matrix coordinate pattern general\n3 3 2\n3 1\n1 2\n");
+		let k2tree = read_mtx::<bool>(&path).unwrap();
+		let mut output = Vec::new();
+		write_mtx_to(&k2tree,&mut output).unwrap();
+		assert_eq!(String::from_utf8(output).unwrap(),"%%MatrixMarket matrix coordinate pattern general\n3 3 2\n1 2\n3 1\n");
+	}
+
+	#[test]
+	fn unsupported_and_malformed(){
+		let path = write_temp("array.mtx","%%MatrixMarket matrix array real general\n2 2\n1\n2\n3\n4\n");
+		assert!(matches!(read_mtx::<Real>(&path),Err(MtxError::Unsupported(_))));
+		let path = write_temp("overflow.mtx","%%MatrixMarket matrix coordinate pattern general\n2 2 1\n3 1\n");
+		assert!(matches!(read_mtx::<bool>(&path),Err(MtxError::Parse(_))));
+		let path = write_temp("short.mtx","%%MatrixMarket matrix coordinate pattern general\n2 2 2\n1 1\n");
+		assert!(matches!(read_mtx::<bool>(&path),Err(MtxError::Header(_))));
+		//A huge declared count is not allocated up front
+		let path = write_temp("huge.mtx","%%MatrixMarket matrix coordinate pattern general\n2 2 18446744073709551615\n1 1\n");
+		assert!(matches!(read_mtx::<bool>(&path),Err(MtxError::Header(_))));
+	}
+}
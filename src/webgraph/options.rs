@@ -0,0 +1,66 @@
+use crate::k2tree::K2tree;
+
+//Graph settings shared by every loader
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct ReadOptions{
+	k:usize,
+	symmetrize:bool,
+	drop_self_loops:bool
+}
+
+impl Default for ReadOptions{
+	fn default()->Self{
+		ReadOptions{
+			k:2,
+			symmetrize:false,
+			drop_self_loops:false
+		}
+	}
+}
+
+impl ReadOptions{
+	pub fn new()->Self{
+		Self::default()
+	}
+	pub fn k(mut self,k:usize)->Self{
+		assert!(k>1,"k must be greater than 1");
+		self.k = k;
+		self
+	}
+	//Read every edge as undirected and build a symmetric tree (upper triangle only)
+	pub fn symmetrize(mut self,symmetrize:bool)->Self{
+		self.symmetrize = symmetrize;
+		self
+	}
+	pub fn drop_self_loops(mut self,drop_self_loops:bool)->Self{
+		self.drop_self_loops = drop_self_loops;
+		self
+	}
+	pub fn get_k(&self)->usize{
+		self.k
+	}
+	pub fn get_symmetrize(&self)->bool{
+		self.symmetrize
+	}
+	pub fn get_drop_self_loops(&self)->bool{
+		self.drop_self_loops
+	}
+
+	//Whether the edge survives drop_self_loops
+	pub(crate) fn keeps(&self,from:usize,to:usize)->bool{
+		!(self.drop_self_loops && from == to)
+	}
+
+	//Tree of the given cells. Symmetric inputs (symmetrize or a symmetric format) keep only
+	//the upper triangle, so they must be square.
+	pub(crate) fn build<W>(&self,rows:usize,cols:usize,background:W,symmetric:bool,
+		triplets:impl IntoIterator<Item=(usize,usize,W)>)->K2tree<W> where W:Eq + Clone{
+		let triplets = triplets.into_iter().filter(|&(i,j,_)| self.keeps(i,j));
+		if symmetric || self.symmetrize{
+			assert_eq!(rows,cols,"symmetric graph must be square");
+			K2tree::from_triplets_symmetric(rows,self.k,background,triplets)
+		}else{
+			K2tree::from_triplets(rows,cols,self.k,background,triplets)
+		}
+	}
+}
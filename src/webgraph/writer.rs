@@ -0,0 +1,140 @@
+use std::fmt::Display;
+use std::hash::Hash;
+use std::io::{self, Write};
+
+use super::ids::IdMap;
+use crate::k2tree::K2tree;
+
+//Output settings shared by the edge list and adjacency list writers
+pub struct WriteOptions<'a>{
+	separator:String,
+	values:bool,
+	labels:Option<Box<dyn Fn(usize)->String + 'a>>
+}
+
+impl <'a> Default for WriteOptions<'a>{
+	fn default()->Self{
+		WriteOptions{
+			separator:String::from("\t"),
+			values:false,
+			labels:None
+		}
+	}
+}
+
+impl <'a> WriteOptions<'a>{
+	pub fn new()->Self{
+		Self::default()
+	}
+	pub fn separator(mut self,separator:&str)->Self{
+		self.separator = String::from(separator);
+		self
+	}
+	//Write the value of each cell next to its target node
+	pub fn values(mut self,values:bool)->Self{
+		self.values = values;
+		self
+	}
+	//Write the original labels instead of the tree indices
+	pub fn ids<L>(self,ids:&'a IdMap<L>)->Self where L:Display + Hash + Eq + Clone{
+		self.labels(move |index| match ids.label_of(index){
+			Some(label) => label.to_string(),
+			None => index.to_string()
+		})
+	}
+	pub fn labels(mut self,labels:impl Fn(usize)->String + 'a)->Self{
+		self.labels = Some(Box::new(labels));
+		self
+	}
+	fn label(&self,index:usize)->String{
+		match &self.labels{
+			Some(labels) => labels(index),
+			None => index.to_string()
+		}
+	}
+}
+
+//One "from to [value]" line per non-background cell, streamed row by row
+pub fn write_edge_list<T,W>(k2tree:&K2tree<T>,writer:&mut W,options:&WriteOptions)->io::Result<()>
+where T:Eq + Clone + Display,W:Write{
+	for i in 0..k2tree.get_rows(){
+		let row = k2tree.row(i);
+		if row.is_empty(){
+			continue;
+		}
+		let from = options.label(i);
+		for (j,value) in row{
+			write!(writer,"{}{}{}",from,options.separator,options.label(j))?;
+			if options.values{
+				write!(writer,"{}{}",options.separator,value)?;
+			}
+			writeln!(writer)?;
+		}
+	}
+	Ok(())
+}
+
+//One line per row: the node followed by its neighbours (each one followed by its value if
+//values are enabled). Rows without neighbours are written as the node alone.
+pub fn write_adjacency_list<T,W>(k2tree:&K2tree<T>,writer:&mut W,options:&WriteOptions)->io::Result<()>
+where T:Eq + Clone + Display,W:Write{
+	for i in 0..k2tree.get_rows(){
+		write!(writer,"{}",options.label(i))?;
+		for (j,value) in k2tree.row(i){
+			write!(writer,"{}{}",options.separator,options.label(j))?;
+			if options.values{
+				write!(writer,"{}{}",options.separator,value)?;
+			}
+		}
+		writeln!(writer)?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+	use crate::k2tree::K2tree;
+	use crate::webgraph::ids::IdMap;
+	use super::{write_edge_list,write_adjacency_list,WriteOptions};
+
+	fn sample()->K2tree<u32>{
+		K2tree::from_triplets(3,3,2,0,vec![(0,1,5),(0,2,6),(2,0,7)])
+	}
+
+	#[test]
+	fn edge_list(){
+		let mut output = Vec::new();
+		write_edge_list(&sample(),&mut output,&WriteOptions::new()).unwrap();
+		assert_eq!(String::from_utf8(output).unwrap(),"0\t1\n0\t2\n2\t0\n");
+
+		let mut output = Vec::new();
+		write_edge_list(&sample(),&mut output,&WriteOptions::new().separator(",").values(true)).unwrap();
+		assert_eq!(String::from_utf8(output).unwrap(),"0,1,5\n0,2,6\n2,0,7\n");
+	}
+
+	#[test]
+	fn adjacency_list(){
+		let mut output = Vec::new();
+		write_adjacency_list(&sample(),&mut output,&WriteOptions::new().separator(" ")).unwrap();
+		assert_eq!(String::from_utf8(output).unwrap(),"0 1 2\n1\n2 0\n");
+
+		let mut output = Vec::new();
+		write_adjacency_list(&sample(),&mut output,&WriteOptions::new().separator(" ").values(true)).unwrap();
+		assert_eq!(String::from_utf8(output).unwrap(),"0 1 5 2 6\n1\n2 0 7\n");
+	}
+
+	#[test]
+	fn original_ids(){
+		let mut ids = IdMap::new();
+		for label in ["a","b","c"]{
+			ids.get_or_insert(String::from(label));
+		}
+		let mut output = Vec::new();
+		write_edge_list(&sample(),&mut output,&WriteOptions::new().ids(&ids)).unwrap();
+		assert_eq!(String::from_utf8(output).unwrap(),"a\tb\na\tc\nc\ta\n");
+
+		let mut output = Vec::new();
+		write_edge_list(&sample(),&mut output,&WriteOptions::new().labels(|index| (index+1).to_string())).unwrap();
+		assert_eq!(String::from_utf8(output).unwrap(),"1\t2\n1\t3\n3\t1\n");
+	}
+}